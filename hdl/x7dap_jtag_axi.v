@@ -0,0 +1,167 @@
+// Copyright 2025 Adam Greig
+// Licensed under the Apache-2.0 and MIT licenses.
+//
+// JTAG-to-AXI4-Lite bridge for `x7dap axi` and `x7dap::axi::AxiBridge`.
+//
+// The bridge uses a BSCANE2 USER data register, 66 bits long and shifted
+// least significant bit first.
+//
+// Bits shifted in:
+//   31:0   Write data
+//   63:32  Address
+//   64     Write (1) or read (0)
+//   65     Start: begin a transaction at Update-DR
+//
+// Bits captured, at Capture-DR:
+//   31:0   Read data from the last transaction
+//   33:32  AXI response from the last transaction
+//   34     Done: the last transaction has completed
+//   65:35  Zero
+//
+// A start request is ignored while a transaction is in progress. Writes
+// always use all four byte strobes. Requests and responses cross between
+// TCK and `aclk` using toggle handshakes, so the clocks are unrelated.
+
+`default_nettype none
+
+module x7dap_jtag_axi #(
+    // BSCANE2 USER register number, 1 to 4.
+    parameter integer USER = 1
+) (
+    input  wire        aclk,
+    input  wire        aresetn,
+
+    output reg  [31:0] m_axi_awaddr,
+    output wire [2:0]  m_axi_awprot,
+    output reg         m_axi_awvalid,
+    input  wire        m_axi_awready,
+
+    output reg  [31:0] m_axi_wdata,
+    output wire [3:0]  m_axi_wstrb,
+    output reg         m_axi_wvalid,
+    input  wire        m_axi_wready,
+
+    input  wire [1:0]  m_axi_bresp,
+    input  wire        m_axi_bvalid,
+    output reg         m_axi_bready,
+
+    output reg  [31:0] m_axi_araddr,
+    output wire [2:0]  m_axi_arprot,
+    output reg         m_axi_arvalid,
+    input  wire        m_axi_arready,
+
+    input  wire [31:0] m_axi_rdata,
+    input  wire [1:0]  m_axi_rresp,
+    input  wire        m_axi_rvalid,
+    output reg         m_axi_rready
+);
+
+    assign m_axi_awprot = 3'b000;
+    assign m_axi_arprot = 3'b000;
+    assign m_axi_wstrb = 4'b1111;
+
+    // TCK domain.
+    wire tck, tdi, sel, capture, shift, update;
+    reg [65:0] sr = 66'd0;
+
+    BSCANE2 #(
+        .JTAG_CHAIN(USER)
+    ) bscan (
+        .CAPTURE(capture),
+        .DRCK(),
+        .RESET(),
+        .RUNTEST(),
+        .SEL(sel),
+        .SHIFT(shift),
+        .TCK(tck),
+        .TDI(tdi),
+        .TMS(),
+        .UPDATE(update),
+        .TDO(sr[0])
+    );
+
+    // Request, held stable from toggling `req` until `ack` follows it.
+    reg [31:0] req_addr = 32'd0;
+    reg [31:0] req_wdata = 32'd0;
+    reg        req_write = 1'b0;
+    reg        req = 1'b0;
+
+    // Response, held stable from toggling `ack` until the next request.
+    reg [31:0] rsp_rdata = 32'd0;
+    reg [1:0]  rsp_resp = 2'b00;
+    reg        ack = 1'b0;
+
+    (* ASYNC_REG = "TRUE" *) reg [1:0] ack_tck = 2'b00;
+    wire done = ack_tck[1] == req;
+
+    always @(posedge tck) begin
+        ack_tck <= {ack_tck[0], ack};
+        if (sel && capture) begin
+            sr <= {31'd0, done, rsp_resp, rsp_rdata};
+        end else if (sel && shift) begin
+            sr <= {tdi, sr[65:1]};
+        end else if (sel && update && sr[65] && done) begin
+            req_wdata <= sr[31:0];
+            req_addr <= sr[63:32];
+            req_write <= sr[64];
+            req <= ~req;
+        end
+    end
+
+    // AXI clock domain.
+    (* ASYNC_REG = "TRUE" *) reg [1:0] req_aclk = 2'b00;
+    reg busy = 1'b0;
+
+    always @(posedge aclk) begin
+        req_aclk <= {req_aclk[0], req};
+        if (!aresetn) begin
+            m_axi_awvalid <= 1'b0;
+            m_axi_wvalid <= 1'b0;
+            m_axi_bready <= 1'b0;
+            m_axi_arvalid <= 1'b0;
+            m_axi_rready <= 1'b0;
+            busy <= 1'b0;
+            // Complete any outstanding request with SLVERR so the host does not wait forever.
+            if (req_aclk[1] != ack) begin
+                rsp_rdata <= 32'd0;
+                rsp_resp <= 2'b10;
+                ack <= req_aclk[1];
+            end
+        end else begin
+            if (!busy && req_aclk[1] != ack) begin
+                busy <= 1'b1;
+                if (req_write) begin
+                    m_axi_awaddr <= req_addr;
+                    m_axi_wdata <= req_wdata;
+                    m_axi_awvalid <= 1'b1;
+                    m_axi_wvalid <= 1'b1;
+                    m_axi_bready <= 1'b1;
+                end else begin
+                    m_axi_araddr <= req_addr;
+                    m_axi_arvalid <= 1'b1;
+                    m_axi_rready <= 1'b1;
+                end
+            end
+            if (m_axi_awvalid && m_axi_awready) m_axi_awvalid <= 1'b0;
+            if (m_axi_wvalid && m_axi_wready) m_axi_wvalid <= 1'b0;
+            if (m_axi_arvalid && m_axi_arready) m_axi_arvalid <= 1'b0;
+            if (m_axi_bready && m_axi_bvalid) begin
+                m_axi_bready <= 1'b0;
+                rsp_rdata <= 32'd0;
+                rsp_resp <= m_axi_bresp;
+                busy <= 1'b0;
+                ack <= ~ack;
+            end
+            if (m_axi_rready && m_axi_rvalid) begin
+                m_axi_rready <= 1'b0;
+                rsp_rdata <= m_axi_rdata;
+                rsp_resp <= m_axi_rresp;
+                busy <= 1'b0;
+                ack <= ~ack;
+            end
+        end
+    end
+
+endmodule
+
+`default_nettype wire
@@ -0,0 +1,138 @@
+// Copyright 2025 Adam Greig
+// Licensed under the Apache-2.0 and MIT licenses.
+//
+// JTAG UART bridge for `x7dap uart` and `x7dap::uart::JtagUart`.
+//
+// The bridge uses a BSCANE2 USER data register, 10 bits long and shifted
+// least significant bit first.
+//
+// Bits shifted in:
+//   7:0    Byte to send to the design
+//   8      Valid: the byte should be sent
+//   9      Zero
+//
+// Bits captured, at Capture-DR:
+//   7:0    Byte received from the design
+//   8      Valid: a byte was received and is now consumed
+//   9      Ready: the design can accept a byte
+//
+// A byte shifted in is only accepted if ready was set in the same shift.
+// Bytes cross between TCK and `clk` using toggle handshakes with one byte
+// of buffering in each direction, so the clocks are unrelated.
+//
+// Bytes from the host appear on the `m_*` stream and bytes for the host
+// are taken from the `s_*` stream, both with AXI4-Stream valid/ready
+// semantics.
+
+`default_nettype none
+
+module x7dap_jtag_uart #(
+    // BSCANE2 USER register number, 1 to 4.
+    parameter integer USER = 3
+) (
+    input  wire       clk,
+    input  wire       rst,
+
+    // Bytes received from the host.
+    output reg  [7:0] m_tdata,
+    output reg        m_tvalid,
+    input  wire       m_tready,
+
+    // Bytes to send to the host.
+    input  wire [7:0] s_tdata,
+    input  wire       s_tvalid,
+    output wire       s_tready
+);
+
+    // TCK domain.
+    wire tck, tdi, sel, capture, shift, update;
+    reg [9:0] sr = 10'd0;
+
+    BSCANE2 #(
+        .JTAG_CHAIN(USER)
+    ) bscan (
+        .CAPTURE(capture),
+        .DRCK(),
+        .RESET(),
+        .RUNTEST(),
+        .SEL(sel),
+        .SHIFT(shift),
+        .TCK(tck),
+        .TDI(tdi),
+        .TMS(),
+        .UPDATE(update),
+        .TDO(sr[0])
+    );
+
+    // Host to design: `h2d_data` is held stable from toggling `h2d_req`
+    // until `h2d_ack` follows it.
+    reg [7:0] h2d_data = 8'd0;
+    reg       h2d_req = 1'b0;
+    reg       h2d_ack = 1'b0;
+    (* ASYNC_REG = "TRUE" *) reg [1:0] h2d_ack_tck = 2'b00;
+    wire h2d_ready = h2d_ack_tck[1] == h2d_req;
+
+    // Ready as reported at Capture-DR, which decides whether the byte
+    // shifted in is accepted at Update-DR.
+    reg captured_ready = 1'b0;
+
+    // Design to host: `d2h_data` is held stable from toggling `d2h_req`
+    // until `d2h_ack` follows it.
+    reg [7:0] d2h_data = 8'd0;
+    reg       d2h_req = 1'b0;
+    reg       d2h_ack = 1'b0;
+    (* ASYNC_REG = "TRUE" *) reg [1:0] d2h_req_tck = 2'b00;
+    wire d2h_valid = d2h_req_tck[1] != d2h_ack;
+
+    always @(posedge tck) begin
+        h2d_ack_tck <= {h2d_ack_tck[0], h2d_ack};
+        d2h_req_tck <= {d2h_req_tck[0], d2h_req};
+        if (sel && capture) begin
+            captured_ready <= h2d_ready;
+            if (d2h_valid) begin
+                sr <= {h2d_ready, 1'b1, d2h_data};
+                d2h_ack <= ~d2h_ack;
+            end else begin
+                sr <= {h2d_ready, 1'b0, 8'd0};
+            end
+        end else if (sel && shift) begin
+            sr <= {tdi, sr[9:1]};
+        end else if (sel && update && sr[8] && captured_ready) begin
+            h2d_data <= sr[7:0];
+            h2d_req <= ~h2d_req;
+            captured_ready <= 1'b0;
+        end
+    end
+
+    // Design clock domain.
+    (* ASYNC_REG = "TRUE" *) reg [1:0] h2d_req_clk = 2'b00;
+    (* ASYNC_REG = "TRUE" *) reg [1:0] d2h_ack_clk = 2'b00;
+
+    assign s_tready = !rst && d2h_ack_clk[1] == d2h_req;
+
+    always @(posedge clk) begin
+        h2d_req_clk <= {h2d_req_clk[0], h2d_req};
+        d2h_ack_clk <= {d2h_ack_clk[0], d2h_ack};
+        if (rst) begin
+            m_tvalid <= 1'b0;
+            // Drop any byte in flight from the host so the bridge is ready again.
+            h2d_ack <= h2d_req_clk[1];
+        end else begin
+            if (m_tvalid && m_tready) begin
+                m_tvalid <= 1'b0;
+            end
+            if ((!m_tvalid || m_tready) && h2d_req_clk[1] != h2d_ack) begin
+                m_tdata <= h2d_data;
+                m_tvalid <= 1'b1;
+                h2d_ack <= ~h2d_ack;
+            end
+            if (s_tvalid && s_tready) begin
+                d2h_data <= s_tdata;
+                d2h_req <= ~d2h_req;
+            end
+        end
+    end
+
+endmodule
+
+`default_nettype wire
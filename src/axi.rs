@@ -0,0 +1,183 @@
+// Copyright 2025 Adam Greig
+// Licensed under the Apache-2.0 and MIT licenses.
+
+//! AXI4-Lite master access through a JTAG-to-AXI bridge in the design.
+//!
+//! The bridge sits on a BSCANE2 USER data register, USER1 by default,
+//! which is 66 bits long, shifted least significant bit first.
+//!
+//! Bits shifted in:
+//!
+//! | Bits    | Field                                     |
+//! |---------|-------------------------------------------|
+//! | 31:0    | Write data                                |
+//! | 63:32   | Address                                   |
+//! | 64      | Write (1) or read (0)                     |
+//! | 65      | Start: begin a transaction at Update-DR   |
+//!
+//! Bits captured, at Capture-DR:
+//!
+//! | Bits    | Field                                     |
+//! |---------|-------------------------------------------|
+//! | 31:0    | Read data from the last transaction       |
+//! | 33:32   | AXI response from the last transaction    |
+//! | 34      | Done: the last transaction has completed  |
+//! | 65:35   | Zero                                      |
+//!
+//! Each transaction is started by one shift with the start bit set, and its
+//! completion polled by further shifts with the start bit clear. Writes
+//! always use all four byte strobes.
+//!
+//! A Verilog implementation of the bridge for 7-series devices is provided
+//! in `hdl/x7dap_jtag_axi.v` in the x7dap repository.
+
+use jtagdap::bitvec::{append_u32, drain_bit, drain_u32, drain_word};
+
+use crate::{Error, Result, X7, tap::Tap};
+
+/// Length of the bridge data register.
+const DR_LEN: usize = 66;
+
+/// Default number of polls before a transaction times out.
+const DEFAULT_POLLS: usize = 16;
+
+/// JTAG-to-AXI bridge on a user data register.
+#[derive(Copy, Clone, Debug)]
+pub struct AxiBridge {
+    user: u8,
+    polls: usize,
+}
+
+impl Default for AxiBridge {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl AxiBridge {
+    /// Create a new bridge accessed through register USER`user`.
+    pub fn new(user: u8) -> Self {
+        AxiBridge { user, polls: DEFAULT_POLLS }
+    }
+
+    /// Set the number of times to poll for completion before timing out.
+    pub fn with_polls(mut self, polls: usize) -> Self {
+        self.polls = polls;
+        self
+    }
+
+    /// Read a 32-bit word from `addr`, which must be word aligned.
+    pub fn read32<T: Tap>(&self, x7: &mut X7<T>, addr: u32) -> Result<u32> {
+        log::debug!("AXI read 0x{addr:08X}");
+        self.transaction(x7, addr, None)
+    }
+
+    /// Write a 32-bit word `data` to `addr`, which must be word aligned.
+    pub fn write32<T: Tap>(&self, x7: &mut X7<T>, addr: u32, data: u32) -> Result<()> {
+        log::debug!("AXI write 0x{addr:08X} = 0x{data:08X}");
+        self.transaction(x7, addr, Some(data))?;
+        Ok(())
+    }
+
+    fn transaction<T: Tap>(&self, x7: &mut X7<T>, addr: u32, wdata: Option<u32>) -> Result<u32> {
+        if !addr.is_multiple_of(4) {
+            return Err(Error::AxiUnaligned(addr));
+        }
+        let mut bits = Vec::with_capacity(DR_LEN);
+        append_u32(&mut bits, wdata.unwrap_or(0));
+        append_u32(&mut bits, addr);
+        bits.push(wdata.is_some());
+        bits.push(true);
+        x7.user_dr(self.user, &bits, DR_LEN)?;
+
+        for _ in 0..self.polls {
+            let captured = x7.user_dr(self.user, &[], DR_LEN)?;
+            let (rdata, rest) = drain_u32(&captured)?;
+            let (resp, rest) = drain_word(rest, 2)?;
+            let (done, _) = drain_bit(rest)?;
+            if done == 1 {
+                return match resp {
+                    0 | 1 => Ok(rdata),
+                    resp => Err(Error::AxiResponse(addr, resp as u8)),
+                };
+            }
+        }
+        Err(Error::AxiTimeout(addr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Command, Family, X7IDCODE, mock::{MockTap, Op}};
+
+    /// Bridge register contents captured with `rdata`, `resp`, and `done`.
+    fn captured(rdata: u32, resp: u8, done: bool) -> Vec<bool> {
+        let mut bits = Vec::new();
+        append_u32(&mut bits, rdata);
+        bits.extend([resp & 1 == 1, resp & 2 == 2, done]);
+        bits.resize(DR_LEN, false);
+        bits
+    }
+
+    /// Request shifted in to start a transaction.
+    fn request(addr: u32, wdata: Option<u32>) -> Vec<bool> {
+        let mut bits = Vec::new();
+        append_u32(&mut bits, wdata.unwrap_or(0));
+        append_u32(&mut bits, addr);
+        bits.extend([wdata.is_some(), true]);
+        bits
+    }
+
+    /// Operations for one shift of `dr` through USER1.
+    fn shift(dr: Vec<bool>) -> [Op; 3] {
+        [Op::WriteIr(Command::USER1.bits(Family::Series7).unwrap()), Op::ExchangeDr(dr), Op::RunTestIdle(1)]
+    }
+
+    #[test]
+    fn write32() {
+        let mut tap = MockTap::new();
+        tap.push_dr(captured(0, 0, true));
+        tap.push_dr(captured(0, 0, false));
+        tap.push_dr(captured(0, 0, true));
+        let mut x7 = X7::new(tap, X7IDCODE::X7A35T);
+        AxiBridge::default().write32(&mut x7, 0x4000_0010, 0xDEAD_BEEF).unwrap();
+        let ops: Vec<Op> = [
+            shift(request(0x4000_0010, Some(0xDEAD_BEEF))),
+            shift(vec![false; DR_LEN]),
+            shift(vec![false; DR_LEN]),
+        ].concat();
+        assert_eq!(x7.tap().ops(), ops);
+        assert_eq!(x7.tap().pending_responses(), 0);
+    }
+
+    #[test]
+    fn read32() {
+        let mut tap = MockTap::new();
+        tap.push_dr(captured(0, 0, true));
+        tap.push_dr(captured(0x1234_5678, 0, true));
+        let mut x7 = X7::new(tap, X7IDCODE::X7A35T);
+        assert_eq!(AxiBridge::default().read32(&mut x7, 0x8000_0000).unwrap(), 0x1234_5678);
+        assert_eq!(x7.tap().ops()[..3], shift(request(0x8000_0000, None)));
+    }
+
+    #[test]
+    fn errors() {
+        // SLVERR.
+        let mut tap = MockTap::new();
+        tap.push_dr(captured(0, 0, true));
+        tap.push_dr(captured(0, 2, true));
+        let mut x7 = X7::new(tap, X7IDCODE::X7A35T);
+        assert!(matches!(AxiBridge::default().read32(&mut x7, 0x10), Err(Error::AxiResponse(0x10, 2))));
+
+        // Never done.
+        let mut x7 = X7::new(MockTap::new(), X7IDCODE::X7A35T);
+        let bridge = AxiBridge::default().with_polls(3);
+        assert!(matches!(bridge.read32(&mut x7, 0x10), Err(Error::AxiTimeout(0x10))));
+        assert_eq!(x7.tap().ir_writes().count(), 4);
+
+        let mut x7 = X7::new(MockTap::new(), X7IDCODE::X7A35T);
+        assert!(matches!(bridge.write32(&mut x7, 0x12, 0), Err(Error::AxiUnaligned(0x12))));
+        assert!(x7.tap().ops().is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+// Copyright 2025 Adam Greig
+// Licensed under the Apache-2.0 and MIT licenses.
+
+//! Boundary-scan access to device pins.
+//!
+//! A `BoundaryScan` maps the cells of the boundary register described by a
+//! BSDL file onto package pin names, and uses the SAMPLE/PRELOAD and EXTEST
+//! instructions to read and drive those pins.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{Error, Result, X7, X7IDCODE, bsdl::{Bsdl, find_bsdl}, tap::Tap};
+
+/// Value to drive onto a pin.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PinDrive {
+    Low,
+    High,
+    HighZ,
+}
+
+impl std::str::FromStr for PinDrive {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "0" | "L" | "LOW" => Ok(PinDrive::Low),
+            "1" | "H" | "HIGH" => Ok(PinDrive::High),
+            "Z" | "HIGHZ" => Ok(PinDrive::HighZ),
+            _ => Err(Error::InvalidPinValue(s.to_string())),
+        }
+    }
+}
+
+/// Boundary-scan cells associated with a single package pin.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pin {
+    /// Package pin name, such as `A1`.
+    pub name: String,
+    /// Port name from the BSDL file.
+    pub port: String,
+    /// Cell which captures the pin state.
+    pub input: Option<usize>,
+    /// Cell which sets the pin output value.
+    pub output: Option<usize>,
+    /// Cell which enables the output driver, and the value which disables it.
+    pub control: Option<(usize, bool)>,
+}
+
+/// Boundary-scan register for one device and package.
+#[derive(Clone, Debug)]
+pub struct BoundaryScan {
+    entity: String,
+    package: String,
+    pins: BTreeMap<String, Pin>,
+    drive: Vec<bool>,
+    driving: bool,
+}
+
+impl BoundaryScan {
+    /// Create a new BoundaryScan from a parsed BSDL file,
+    /// using `package` or the file's default package.
+    ///
+    /// All cells are initially loaded with their safe values.
+    pub fn new(bsdl: &Bsdl, package: Option<&str>) -> Result<Self> {
+        let (package, pin_map) = bsdl.pin_map(package)?;
+        let mut pins: BTreeMap<String, Pin> = BTreeMap::new();
+
+        for cell in bsdl.cells() {
+            let Some(port) = &cell.port else { continue };
+            let Some(name) = package_pin(&pin_map, port) else {
+                log::debug!("No package pin for port {}", port);
+                continue;
+            };
+            let pin = pins.entry(name.clone()).or_insert_with(|| Pin {
+                name, port: port.clone(), input: None, output: None, control: None,
+            });
+            if cell.function.is_input() {
+                pin.input = Some(cell.number);
+            }
+            if cell.function.is_output() {
+                pin.output = Some(cell.number);
+                pin.control = cell.control;
+            }
+        }
+
+        // Load safe values, disabling outputs where no safe value is given.
+        let mut drive: Vec<bool> = bsdl.cells().iter().map(|c| c.safe.unwrap_or(false)).collect();
+        for cell in bsdl.cells() {
+            if let Some((ccell, disable)) = cell.control && bsdl.cells()[ccell].safe.is_none() {
+                drive[ccell] = disable;
+            }
+        }
+
+        Ok(BoundaryScan {
+            entity: bsdl.entity().to_string(), package, pins, drive, driving: false,
+        })
+    }
+
+    /// Find the BSDL file for `idcode` using `bsdl::find_bsdl`, and load its
+    /// boundary-scan map for `package`.
+    ///
+    /// If `package` is None, the device must have a single BSDL file.
+    pub fn for_device(idcode: X7IDCODE, package: Option<&str>) -> Result<Self> {
+        let bsdl = Bsdl::from_path(find_bsdl(idcode, package)?)?;
+        Self::new(&bsdl, package)
+    }
+
+    /// Check this map was generated for `idcode`, using the BSDL entity name,
+    /// which starts with the part name such as `XC7A35T`.
+    pub fn check_device(self, idcode: X7IDCODE) -> Result<Self> {
+        let part = format!("XC{}", &idcode.name()[1..]);
+        let entity = self.entity.to_ascii_uppercase();
+        match entity.strip_prefix(&part) {
+            Some(rest) if rest.is_empty() || rest.starts_with('_') => Ok(self),
+            _ => Err(Error::BsrDeviceMismatch { entity: self.entity, device: idcode.name() }),
+        }
+    }
+
+    /// Load a boundary-scan map in the compact format produced by `to_compact`.
+    pub fn from_compact(src: &str) -> Result<Self> {
+        let err = |line: &str| Error::BsdlParse(format!("invalid compact map line: {line}"));
+        let mut lines = src.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#'));
+        if lines.next() != Some("x7dap-bsr 1") {
+            return Err(Error::BsdlParse("missing compact map header".to_string()));
+        }
+        let mut entity = String::new();
+        let mut package = String::new();
+        let mut drive = Vec::new();
+        let mut pins = BTreeMap::new();
+        for line in lines {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                ["entity", name] => entity = name.to_string(),
+                ["package", name] => package = name.to_string(),
+                ["safe", len, hex] => {
+                    let len: usize = len.parse().map_err(|_| err(line))?;
+                    if hex.len() * 4 < len {
+                        return Err(err(line));
+                    }
+                    drive = (0..len).map(|idx| {
+                        let nibble = hex.as_bytes()[idx / 4] as char;
+                        nibble.to_digit(16).map(|n| (n >> (idx % 4)) & 1 == 1).ok_or(err(line))
+                    }).collect::<Result<_>>()?;
+                },
+                ["pin", name, port, input, output, control] => {
+                    let cell = |f: &str| -> Result<Option<usize>> {
+                        if f == "-" { Ok(None) } else { f.parse().map(Some).map_err(|_| err(line)) }
+                    };
+                    let control = match control {
+                        "-" => None,
+                        c => {
+                            let (cell, disable) = c.split_once(':').ok_or(err(line))?;
+                            Some((cell.parse().map_err(|_| err(line))?, disable == "1"))
+                        },
+                    };
+                    pins.insert(name.to_string(), Pin {
+                        name: name.to_string(),
+                        port: port.to_string(),
+                        input: cell(input)?,
+                        output: cell(output)?,
+                        control,
+                    });
+                },
+                _ => return Err(err(line)),
+            }
+        }
+        let len = drive.len();
+        let cells = pins.values().flat_map(|p| [p.input, p.output, p.control.map(|c| c.0)]);
+        if cells.flatten().any(|cell| cell >= len) {
+            return Err(Error::BsdlParse("compact map cell out of range".to_string()));
+        }
+        Ok(BoundaryScan { entity, package, pins, drive, driving: false })
+    }
+
+    /// Serialise this map to a compact line-based text format.
+    ///
+    /// The format stores only the safe boundary register contents and the
+    /// cells for each package pin, which is all that is required to sample
+    /// and drive pins.
+    pub fn to_compact(&self) -> String {
+        let mut out = String::from("x7dap-bsr 1\n");
+        out.push_str(&format!("entity {}\npackage {}\n", self.entity, self.package));
+        let hex: String = self.drive.chunks(4).map(|bits| {
+            let nibble = bits.iter().enumerate().fold(0, |n, (idx, b)| n | ((*b as u32) << idx));
+            char::from_digit(nibble, 16).unwrap()
+        }).collect();
+        out.push_str(&format!("safe {} {}\n", self.drive.len(), hex));
+        let cell = |c: Option<usize>| c.map(|c| c.to_string()).unwrap_or("-".to_string());
+        for pin in self.pins.values() {
+            let control = match pin.control {
+                Some((cell, disable)) => format!("{}:{}", cell, disable as u8),
+                None => "-".to_string(),
+            };
+            out.push_str(&format!("pin {} {} {} {} {}\n",
+                pin.name, pin.port, cell(pin.input), cell(pin.output), control));
+        }
+        out
+    }
+
+    /// BSDL entity this map was generated from.
+    pub fn entity(&self) -> &str {
+        &self.entity
+    }
+
+    /// Package this map describes.
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    /// Boundary register length in bits.
+    pub fn len(&self) -> usize {
+        self.drive.len()
+    }
+
+    /// Whether the boundary register is empty.
+    pub fn is_empty(&self) -> bool {
+        self.drive.is_empty()
+    }
+
+    /// All mapped pins, sorted by name.
+    pub fn pins(&self) -> impl Iterator<Item = &Pin> {
+        self.pins.values()
+    }
+
+    /// Look up a pin by package pin name or port name.
+    pub fn pin(&self, name: &str) -> Result<&Pin> {
+        let name = name.to_ascii_uppercase();
+        self.pins.get(&name)
+            .or_else(|| self.pins.values().find(|p| p.port == name))
+            .ok_or(Error::UnknownPin(name))
+    }
+
+    /// Current boundary register contents to be driven, in order from TDO.
+    pub fn drive_vector(&self) -> &[bool] {
+        &self.drive
+    }
+
+    /// Set the value to drive onto `pin` on the next call to `apply`.
+    pub fn set(&mut self, pin: &str, value: PinDrive) -> Result<()> {
+        let pin = self.pin(pin)?.clone();
+        let (output, (control, disable)) = match (pin.output, pin.control) {
+            (Some(output), Some(control)) => (output, control),
+            (Some(output), None) if value != PinDrive::HighZ => {
+                self.drive[output] = value == PinDrive::High;
+                return Ok(());
+            },
+            _ => return Err(Error::PinNotDriveable(pin.name)),
+        };
+        match value {
+            PinDrive::HighZ => self.drive[control] = disable,
+            PinDrive::Low | PinDrive::High => {
+                self.drive[control] = !disable;
+                self.drive[output] = value == PinDrive::High;
+            },
+        }
+        Ok(())
+    }
+
+    /// Decode captured boundary register contents into pin states,
+    /// for all pins with an input cell.
+    pub fn decode(&self, captured: &[bool]) -> BTreeMap<String, bool> {
+        self.pins.values()
+            .filter_map(|pin| pin.input.map(|cell| (pin.name.clone(), captured[cell])))
+            .collect()
+    }
+
+    /// Sample all pin states with SAMPLE/PRELOAD, without affecting device operation.
+    pub fn sample<T: Tap>(&self, x7: &mut X7<T>) -> Result<BTreeMap<String, bool>> {
+        let captured = x7.sample(&self.drive)?;
+        Ok(self.decode(&captured))
+    }
+
+    /// Drive all pins set with `set` using EXTEST, returning the pin states
+    /// captured after the new values were applied.
+    ///
+    /// The device stops normal operation and pins remain driven until
+    /// `release` is called.
+    pub fn apply<T: Tap>(&mut self, x7: &mut X7<T>) -> Result<BTreeMap<String, bool>> {
+        if !self.driving {
+            // Preload the update latches so outputs do not glitch on entering EXTEST.
+            x7.preload(&self.drive)?;
+            self.driving = true;
+        }
+        x7.extest(&self.drive)?;
+        let captured = x7.extest(&self.drive)?;
+        Ok(self.decode(&captured))
+    }
+
+    /// Stop driving pins and return the device to normal operation.
+    pub fn release<T: Tap>(&mut self, x7: &mut X7<T>) -> Result<()> {
+        self.driving = false;
+        x7.test_logic_reset()
+    }
+}
+
+/// Find the package pin for `port`, which may be an element of a vector port.
+fn package_pin(pin_map: &HashMap<String, Vec<String>>, port: &str) -> Option<String> {
+    if let Some((base, idx)) = port.strip_suffix(')').and_then(|p| p.split_once('(')) {
+        let idx: usize = idx.trim().parse().ok()?;
+        pin_map.get(base.trim())?.get(idx).cloned()
+    } else {
+        pin_map.get(port)?.first().cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = "x7dap-bsr 1
+        entity XC7A35T_CSG324
+        package CSG324
+        safe 4 9
+        pin A1 IO_A1 0 1 3:1
+        pin B2 DONE 2 - -
+    ";
+
+    #[test]
+    fn compact_round_trip() {
+        let bscan = BoundaryScan::from_compact(MAP).unwrap();
+        assert_eq!(bscan.len(), 4);
+        assert_eq!(bscan.drive_vector(), [true, false, false, true]);
+        assert_eq!(bscan.pin("io_a1").unwrap().name, "A1");
+        let again = BoundaryScan::from_compact(&bscan.to_compact()).unwrap();
+        assert_eq!(again.to_compact(), bscan.to_compact());
+    }
+
+    #[test]
+    fn check_device_entity() {
+        let bscan = BoundaryScan::from_compact(MAP).unwrap();
+        assert!(bscan.clone().check_device(X7IDCODE::X7A35T).is_ok());
+        assert!(matches!(bscan.check_device(X7IDCODE::X7A50T),
+                         Err(Error::BsrDeviceMismatch { .. })));
+    }
+
+    #[test]
+    fn drive_and_decode() {
+        let mut bscan = BoundaryScan::from_compact(MAP).unwrap();
+        bscan.set("A1", PinDrive::Low).unwrap();
+        assert_eq!(bscan.drive_vector(), [true, false, false, false]);
+        bscan.set("A1", PinDrive::HighZ).unwrap();
+        assert!(bscan.drive_vector()[3]);
+        assert!(matches!(bscan.set("B2", PinDrive::High), Err(Error::PinNotDriveable(_))));
+        let states = bscan.decode(&[true, false, false, false]);
+        assert!(states["A1"]);
+        assert!(!states["B2"]);
+    }
+}
@@ -0,0 +1,583 @@
+// Copyright 2025 Adam Greig
+// Licensed under the Apache-2.0 and MIT licenses.
+
+//! Minimal BSDL parser.
+//!
+//! Only the attributes required for boundary-scan operations are extracted:
+//! the instruction length and opcodes, the IDCODE register, the boundary
+//! register cells, and the pin maps for each package.
+
+use std::{collections::HashMap, env, fs, path::{Path, PathBuf}};
+use jtagdap::jtag::IDCODE;
+
+use crate::{Error, Result, X7IDCODE};
+
+/// Function of a single boundary-scan cell.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CellFunction {
+    Input,
+    Clock,
+    Output2,
+    Output3,
+    Control,
+    ControlR,
+    Internal,
+    Bidir,
+    ObserveOnly,
+}
+
+impl CellFunction {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "INPUT" => Some(CellFunction::Input),
+            "CLOCK" => Some(CellFunction::Clock),
+            "OUTPUT2" => Some(CellFunction::Output2),
+            "OUTPUT3" => Some(CellFunction::Output3),
+            "CONTROL" => Some(CellFunction::Control),
+            "CONTROLR" => Some(CellFunction::ControlR),
+            "INTERNAL" => Some(CellFunction::Internal),
+            "BIDIR" => Some(CellFunction::Bidir),
+            "OBSERVE_ONLY" => Some(CellFunction::ObserveOnly),
+            _ => None,
+        }
+    }
+
+    /// Whether this cell captures the state of its port.
+    pub fn is_input(&self) -> bool {
+        matches!(self, CellFunction::Input | CellFunction::Clock |
+                       CellFunction::Bidir | CellFunction::ObserveOnly)
+    }
+
+    /// Whether this cell drives its port.
+    pub fn is_output(&self) -> bool {
+        matches!(self, CellFunction::Output2 | CellFunction::Output3 | CellFunction::Bidir)
+    }
+}
+
+/// A single cell in the boundary register.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoundaryCell {
+    /// Cell number, where cell 0 is closest to TDO.
+    pub number: usize,
+    /// Port name, or None for cells not associated with a port.
+    pub port: Option<String>,
+    pub function: CellFunction,
+    /// Safe value to load into the cell, or None if any value is safe.
+    pub safe: Option<bool>,
+    /// Control cell number and the value which disables this output.
+    pub control: Option<(usize, bool)>,
+}
+
+/// Contents of a parsed BSDL file.
+#[derive(Clone, Debug)]
+pub struct Bsdl {
+    entity: String,
+    default_package: Option<String>,
+    instruction_length: usize,
+    instructions: Vec<(String, Vec<String>)>,
+    idcode: Option<String>,
+    cells: Vec<BoundaryCell>,
+    pin_maps: HashMap<String, Vec<(String, Vec<String>)>>,
+}
+
+impl Bsdl {
+    /// Load and parse a BSDL file from the provided path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse BSDL source text.
+    pub fn parse(src: &str) -> Result<Self> {
+        let tokens = tokenise(src)?;
+        let mut entity = None;
+        let mut default_package = None;
+        let mut instruction_length = None;
+        let mut instructions = Vec::new();
+        let mut boundary_length = None;
+        let mut idcode = None;
+        let mut cells = Vec::new();
+        let mut pin_maps = HashMap::new();
+
+        for stmt in tokens.split(|t| *t == Token::Punct(';')) {
+            match stmt {
+                [Token::Ident(kw), Token::Ident(name), Token::Ident(is), rest @ ..]
+                    if kw.eq_ignore_ascii_case("entity") && is.eq_ignore_ascii_case("is") =>
+                {
+                    entity = Some(name.clone());
+                    // Find the default package from the PHYSICAL_PIN_MAP generic.
+                    if let Some(pos) = rest.iter().position(|t| t.is_ident("PHYSICAL_PIN_MAP"))
+                        && let Some(Token::Str(pkg)) = rest[pos..].iter()
+                            .skip_while(|t| **t != Token::Assign).nth(1)
+                    {
+                        default_package = Some(pkg.clone());
+                    }
+                },
+                [Token::Ident(kw), Token::Ident(attr), Token::Ident(of), _,
+                 Token::Punct(':'), Token::Ident(_), Token::Ident(is), value @ ..]
+                    if kw.eq_ignore_ascii_case("attribute") && of.eq_ignore_ascii_case("of")
+                        && is.eq_ignore_ascii_case("is") =>
+                {
+                    match attr.to_ascii_uppercase().as_str() {
+                        "INSTRUCTION_LENGTH" => instruction_length = Some(number(value)?),
+                        "BOUNDARY_LENGTH" => boundary_length = Some(number(value)?),
+                        "INSTRUCTION_OPCODE" => instructions = split_entries(&string(value)?)?,
+                        "IDCODE_REGISTER" => idcode = Some(string(value)?),
+                        "BOUNDARY_REGISTER" => cells = parse_cells(&string(value)?)?,
+                        _ => (),
+                    }
+                },
+                [Token::Ident(kw), Token::Ident(name), Token::Punct(':'),
+                 Token::Ident(ty), Token::Assign, value @ ..]
+                    if kw.eq_ignore_ascii_case("constant")
+                        && ty.eq_ignore_ascii_case("PIN_MAP_STRING") =>
+                {
+                    pin_maps.insert(name.to_ascii_uppercase(), parse_pin_map(&string(value)?)?);
+                },
+                _ => (),
+            }
+        }
+
+        let entity = entity.ok_or_else(|| bsdl_error("no entity declaration"))?;
+        let instruction_length = instruction_length
+            .ok_or_else(|| bsdl_error("no INSTRUCTION_LENGTH attribute"))?;
+        let boundary_length = boundary_length
+            .ok_or_else(|| bsdl_error("no BOUNDARY_LENGTH attribute"))?;
+        if cells.len() != boundary_length {
+            return Err(bsdl_error(&format!(
+                "BOUNDARY_LENGTH is {} but {} cells are defined", boundary_length, cells.len())));
+        }
+        cells.sort_by_key(|c| c.number);
+        if cells.iter().enumerate().any(|(idx, c)| c.number != idx) {
+            return Err(bsdl_error("boundary register cells are not numbered contiguously"));
+        }
+        if let Some(cell) = cells.iter().find(|c| c.control.is_some_and(|(cc, _)| cc >= boundary_length)) {
+            return Err(bsdl_error(&format!(
+                "cell {} has control cell {} outside the boundary register",
+                cell.number, cell.control.unwrap().0)));
+        }
+
+        log::debug!("Parsed BSDL for {}: {} cells, packages {:?}",
+                    entity, cells.len(), pin_maps.keys().collect::<Vec<_>>());
+
+        Ok(Bsdl {
+            entity, default_package, instruction_length, instructions, idcode, cells, pin_maps,
+        })
+    }
+
+    /// Name of the entity described by this file.
+    pub fn entity(&self) -> &str {
+        &self.entity
+    }
+
+    /// Instruction register length in bits.
+    pub fn instruction_length(&self) -> usize {
+        self.instruction_length
+    }
+
+    /// Opcode bit strings for the named instruction, MSbit first.
+    pub fn instruction(&self, name: &str) -> Option<&[String]> {
+        self.instructions.iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, codes)| &codes[..])
+    }
+
+    /// Boundary register length in bits.
+    pub fn boundary_length(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// All boundary register cells, in order from TDO.
+    pub fn cells(&self) -> &[BoundaryCell] {
+        &self.cells
+    }
+
+    /// Check whether `idcode` matches the IDCODE_REGISTER attribute.
+    ///
+    /// Returns true if the file does not specify an IDCODE.
+    pub fn matches_idcode(&self, idcode: IDCODE) -> bool {
+        let Some(pattern) = &self.idcode else { return true };
+        pattern.chars().filter(|c| !c.is_whitespace()).rev().enumerate().all(|(bit, c)| {
+            match c {
+                '0' => (idcode.0 >> bit) & 1 == 0,
+                '1' => (idcode.0 >> bit) & 1 == 1,
+                _ => true,
+            }
+        })
+    }
+
+    /// Names of all packages with a pin map in this file.
+    pub fn packages(&self) -> Vec<&str> {
+        let mut packages: Vec<&str> = self.pin_maps.keys().map(|k| k.as_str()).collect();
+        packages.sort();
+        packages
+    }
+
+    /// Package selected by the PHYSICAL_PIN_MAP generic, if specified.
+    pub fn default_package(&self) -> Option<&str> {
+        self.default_package.as_deref()
+    }
+
+    /// Map from port name to package pin names for `package`,
+    /// or for the default package if `package` is None.
+    ///
+    /// Returns the selected package name and its pin map.
+    pub fn pin_map(&self, package: Option<&str>)
+        -> Result<(String, HashMap<String, Vec<String>>)>
+    {
+        let package = match package.or(self.default_package.as_deref()) {
+            Some(package) => package.to_ascii_uppercase(),
+            None if self.pin_maps.len() == 1 => self.pin_maps.keys().next().unwrap().clone(),
+            None => return Err(Error::UnknownPackage("<none specified>".to_string())),
+        };
+        let map = self.pin_maps.get(&package)
+            .ok_or_else(|| Error::UnknownPackage(package.clone()))?;
+        let map = map.iter().map(|(port, pins)| (port.to_ascii_uppercase(), pins.clone()));
+        Ok((package, map.collect()))
+    }
+}
+
+/// Search for the BSDL file for `idcode` in `package`.
+///
+/// Directories listed in the `X7DAP_BSDL_PATH` environment variable are
+/// searched first, followed by the BSDL directories of a Vivado installation
+/// given by `XILINX_VIVADO`. Files are expected to be named as in Vivado,
+/// for example `xc7a35t_csg324.bsd`. If `package` is None, a unique file
+/// for the device must be found.
+pub fn find_bsdl(idcode: X7IDCODE, package: Option<&str>) -> Result<PathBuf> {
+    let mut dirs: Vec<PathBuf> = env::var_os("X7DAP_BSDL_PATH")
+        .map(|paths| env::split_paths(&paths).collect())
+        .unwrap_or_default();
+    if let Some(vivado) = env::var_os("XILINX_VIVADO") {
+        let parts = Path::new(&vivado).join("data").join("parts").join("xilinx");
+        if let Ok(families) = fs::read_dir(parts) {
+            dirs.extend(families.flatten().map(|f| f.path().join("public").join("bsdl")));
+        }
+    }
+
+    let part = format!("xc{}", &idcode.name()[1..]).to_ascii_lowercase();
+    let prefix = format!("{part}_");
+    let mut found = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_ascii_lowercase();
+            let Some(pkg) = name.strip_prefix(&prefix).and_then(|n| n.strip_suffix(".bsd")) else {
+                continue;
+            };
+            if package.is_none_or(|p| p.eq_ignore_ascii_case(pkg)) {
+                found.push(entry.path());
+            }
+        }
+        if !found.is_empty() {
+            break;
+        }
+    }
+
+    match found.len() {
+        0 => Err(Error::BsdlNotFound(part)),
+        1 => {
+            log::info!("Using BSDL file {}", found[0].display());
+            Ok(found.remove(0))
+        },
+        _ => {
+            log::error!("Multiple BSDL files found, specify one using --package: {:?}", found);
+            Err(Error::BsdlNotFound(part))
+        },
+    }
+}
+
+fn bsdl_error(msg: &str) -> Error {
+    Error::BsdlParse(msg.to_string())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(String),
+    Str(String),
+    Assign,
+    Punct(char),
+}
+
+impl Token {
+    fn is_ident(&self, name: &str) -> bool {
+        matches!(self, Token::Ident(ident) if ident.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Split BSDL source into tokens, discarding comments.
+fn tokenise(src: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = src.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => (),
+            '-' if chars.peek() == Some(&'-') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            },
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => s.push(c),
+                        None => return Err(bsdl_error("unterminated string")),
+                    }
+                }
+                tokens.push(Token::Str(s));
+            },
+            ':' if chars.peek() == Some(&'=') => {
+                chars.next();
+                tokens.push(Token::Assign);
+            },
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut s = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    s.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Ident(s));
+            },
+            c if c.is_ascii_digit() => {
+                let mut s = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '.') {
+                        break;
+                    }
+                    s.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Number(s));
+            },
+            c => tokens.push(Token::Punct(c)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Interpret an attribute value as a single integer.
+fn number(value: &[Token]) -> Result<usize> {
+    match value {
+        [Token::Number(n)] => n.parse().map_err(|_| bsdl_error(&format!("invalid number {n}"))),
+        _ => Err(bsdl_error("expected a number")),
+    }
+}
+
+/// Interpret an attribute value as a concatenation of strings.
+fn string(value: &[Token]) -> Result<String> {
+    let mut s = String::new();
+    for token in value {
+        match token {
+            Token::Str(part) => s.push_str(part),
+            Token::Punct('&') => (),
+            _ => return Err(bsdl_error(&format!("unexpected {token:?} in string"))),
+        }
+    }
+    Ok(s)
+}
+
+/// Split a string of the form `NAME (a, b, c), NAME (d, e)` into names and fields.
+fn split_entries(s: &str) -> Result<Vec<(String, Vec<String>)>> {
+    let mut entries = Vec::new();
+    let mut rest = s;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+        if rest.is_empty() {
+            return Ok(entries);
+        }
+        let open = rest.find('(').ok_or_else(|| bsdl_error(&format!("expected ( in {rest}")))?;
+        let name = rest[..open].trim().to_string();
+        let (fields, remaining) = split_group(&rest[open..])?;
+        entries.push((name, fields));
+        rest = remaining;
+    }
+}
+
+/// Split a parenthesised group `(a, b(1), c)` into its top-level fields,
+/// returning the fields and the remainder of the string after the group.
+fn split_group(s: &str) -> Result<(Vec<String>, &str)> {
+    let mut depth = 0;
+    let mut fields = Vec::new();
+    let mut start = 1;
+    for (idx, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    fields.push(s[start..idx].trim().to_string());
+                    return Ok((fields, &s[idx + 1..]));
+                }
+            },
+            ',' if depth == 1 => {
+                fields.push(s[start..idx].trim().to_string());
+                start = idx + 1;
+            },
+            _ => (),
+        }
+    }
+    Err(bsdl_error("unbalanced parentheses"))
+}
+
+fn parse_cells(s: &str) -> Result<Vec<BoundaryCell>> {
+    split_entries(s)?.into_iter().map(|(number, fields)| {
+        let number = number.parse()
+            .map_err(|_| bsdl_error(&format!("invalid cell number {number}")))?;
+        if fields.len() != 4 && fields.len() != 7 {
+            return Err(bsdl_error(&format!("cell {number} has {} fields", fields.len())));
+        }
+        let port = match fields[1].as_str() {
+            "*" => None,
+            port => Some(port.to_ascii_uppercase()),
+        };
+        let function = CellFunction::from_name(&fields[2])
+            .ok_or_else(|| bsdl_error(&format!("unknown cell function {}", fields[2])))?;
+        let safe = bit_value(&fields[3]);
+        let control = if fields.len() == 7 {
+            let ccell = fields[4].parse()
+                .map_err(|_| bsdl_error(&format!("invalid control cell {}", fields[4])))?;
+            let disable = bit_value(&fields[5])
+                .ok_or_else(|| bsdl_error(&format!("invalid disable value {}", fields[5])))?;
+            Some((ccell, disable))
+        } else {
+            None
+        };
+        Ok(BoundaryCell { number, port, function, safe, control })
+    }).collect()
+}
+
+fn bit_value(s: &str) -> Option<bool> {
+    match s {
+        "0" => Some(false),
+        "1" => Some(true),
+        _ => None,
+    }
+}
+
+fn parse_pin_map(s: &str) -> Result<Vec<(String, Vec<String>)>> {
+    let mut map = Vec::new();
+    let mut rest = s.trim();
+    while !rest.is_empty() {
+        let colon = rest.find(':').ok_or_else(|| bsdl_error(&format!("expected : in {rest}")))?;
+        let port = rest[..colon].trim().to_string();
+        rest = rest[colon + 1..].trim_start();
+        if rest.starts_with('(') {
+            let (pins, remaining) = split_group(rest)?;
+            map.push((port, pins));
+            rest = remaining;
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            map.push((port, vec![rest[..end].trim().to_string()]));
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BSDL: &str = r#"
+        -- Minimal example device.
+        entity TEST_DEV is
+            generic (PHYSICAL_PIN_MAP : string := "PKG8");
+            port (A: inout bit; B: in bit; LED: out bit_vector(0 to 1); TCK: in bit);
+            use STD_1149_1_2001.all;
+            attribute COMPONENT_CONFORMANCE of TEST_DEV : entity is "STD_1149_1_2001";
+            attribute PIN_MAP of TEST_DEV : entity is PHYSICAL_PIN_MAP;
+            constant PKG8: PIN_MAP_STRING :=
+                "A: 1, B: 2, " &
+                "LED: (3, 4), TCK: 5";
+            attribute INSTRUCTION_LENGTH of TEST_DEV : entity is 6;
+            attribute INSTRUCTION_OPCODE of TEST_DEV : entity is
+                "EXTEST (100110)," &
+                "SAMPLE (000001, 000010)," &
+                "BYPASS (111111)";
+            attribute IDCODE_REGISTER of TEST_DEV : entity is
+                "XXXX0011011000101101000010010011";
+            attribute BOUNDARY_LENGTH of TEST_DEV : entity is 6;
+            attribute BOUNDARY_REGISTER of TEST_DEV : entity is
+                "0 (BC_2, *, controlr, 1)," &
+                "1 (BC_2, A, bidir, X, 0, 1, Z)," &
+                "2 (BC_2, B, input, X)," &
+                "3 (BC_2, LED(0), output3, X, 5, 1, Z)," &
+                "4 (BC_2, LED(1), output2, 0)," &
+                "5 (BC_2, *, control, 1)";
+        end TEST_DEV;
+    "#;
+
+    #[test]
+    fn parse_attributes() {
+        let bsdl = Bsdl::parse(BSDL).unwrap();
+        assert_eq!(bsdl.entity(), "TEST_DEV");
+        assert_eq!(bsdl.default_package(), Some("PKG8"));
+        assert_eq!(bsdl.instruction_length(), 6);
+        assert_eq!(bsdl.instruction("sample").unwrap(), ["000001", "000010"]);
+        assert!(bsdl.instruction("IDCODE").is_none());
+        assert_eq!(bsdl.packages(), ["PKG8"]);
+    }
+
+    #[test]
+    fn parse_cells() {
+        let bsdl = Bsdl::parse(BSDL).unwrap();
+        let cells = bsdl.cells();
+        assert_eq!(bsdl.boundary_length(), 6);
+        assert_eq!(cells[0].port, None);
+        assert_eq!(cells[0].function, CellFunction::ControlR);
+        assert_eq!(cells[0].safe, Some(true));
+        assert_eq!(cells[1].port.as_deref(), Some("A"));
+        assert_eq!(cells[1].function, CellFunction::Bidir);
+        assert_eq!(cells[1].safe, None);
+        assert_eq!(cells[1].control, Some((0, true)));
+        assert_eq!(cells[3].port.as_deref(), Some("LED(0)"));
+        assert_eq!(cells[3].control, Some((5, true)));
+        assert_eq!(cells[4].control, None);
+    }
+
+    #[test]
+    fn parse_pin_map() {
+        let bsdl = Bsdl::parse(BSDL).unwrap();
+        let (package, map) = bsdl.pin_map(None).unwrap();
+        assert_eq!(package, "PKG8");
+        assert_eq!(map["A"], ["1"]);
+        assert_eq!(map["LED"], ["3", "4"]);
+        assert!(matches!(bsdl.pin_map(Some("PKG9")), Err(Error::UnknownPackage(_))));
+    }
+
+    #[test]
+    fn match_idcode() {
+        let bsdl = Bsdl::parse(BSDL).unwrap();
+        assert!(bsdl.matches_idcode(IDCODE(0x0362D093)));
+        assert!(bsdl.matches_idcode(IDCODE(0x5362D093)));
+        assert!(!bsdl.matches_idcode(IDCODE(0x0362C093)));
+    }
+
+    #[test]
+    fn reject_control_cell_out_of_range() {
+        let src = BSDL.replace("X, 5, 1, Z", "X, 6, 1, Z");
+        assert!(matches!(Bsdl::parse(&src), Err(Error::BsdlParse(_))));
+    }
+
+    #[test]
+    fn reject_wrong_boundary_length() {
+        let src = BSDL.replace("entity is 6;\n            attribute BOUNDARY_REGISTER",
+                               "entity is 7;\n            attribute BOUNDARY_REGISTER");
+        assert!(matches!(Bsdl::parse(&src), Err(Error::BsdlParse(_))));
+    }
+
+    #[test]
+    fn reject_unterminated_string() {
+        assert!(matches!(Bsdl::parse("entity X is \"abc"), Err(Error::BsdlParse(_))));
+    }
+}
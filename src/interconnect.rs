@@ -0,0 +1,359 @@
+// Copyright 2025 Adam Greig
+// Licensed under the Apache-2.0 and MIT licenses.
+
+//! Board interconnect testing using boundary scan.
+//!
+//! A netlist lists which package pins are connected together, one net per line:
+//!
+//! ```text
+//! # Comments start with a hash.
+//! LED0: A1 B2
+//! I2C_SDA: C3 D4 PULLUP
+//! GND: E5 GND
+//! ```
+//!
+//! A net may be marked `PULLUP` or `PULLDOWN` if it has a resistor to a rail,
+//! or `VCC` or `GND` if it is tied directly to a rail.
+//!
+//! Each drivable net is driven in turn with walking-ones and walking-zeros
+//! patterns while all other nets are driven to the opposite value, and
+//! every receiving pin is checked. Only one pin per net is driven at a time.
+
+use std::{collections::BTreeSet, fs, path::Path};
+
+use crate::{Error, Result, X7, bscan::{BoundaryScan, PinDrive}, tap::Tap};
+
+/// Connection of a net to a supply rail.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Tie {
+    PullUp,
+    PullDown,
+    Vcc,
+    Gnd,
+}
+
+impl Tie {
+    /// Value the net should read when no pin is driving it.
+    fn idle_value(&self) -> bool {
+        matches!(self, Tie::PullUp | Tie::Vcc)
+    }
+
+    /// Whether the net can be driven by a pin.
+    fn drivable(&self) -> bool {
+        matches!(self, Tie::PullUp | Tie::PullDown)
+    }
+}
+
+/// A single net in the netlist.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Net {
+    pub name: String,
+    pub pins: Vec<String>,
+    pub tie: Option<Tie>,
+}
+
+/// A fault found by the interconnect test.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fault {
+    /// A pin did not follow its net's driver.
+    Open { net: String, pin: String },
+    /// Two nets are connected to each other.
+    Short { net: String, other: String },
+    /// A net could not be driven to `value`, including at its driving pin.
+    Stuck { net: String, value: bool },
+    /// A net did not read its pull-up, pull-down, or rail value while undriven.
+    Tie { net: String, pin: String },
+}
+
+impl std::fmt::Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Fault::Open { net, pin } => write!(f, "Open: pin {pin} on net {net}"),
+            Fault::Short { net, other } => write!(f, "Short: net {net} to net {other}"),
+            Fault::Stuck { net, value } => {
+                write!(f, "Stuck: net {net} cannot be driven {}", *value as u8)
+            },
+            Fault::Tie { net, pin } => write!(f, "Tie: pin {pin} on net {net} has wrong idle value"),
+        }
+    }
+}
+
+/// Netlist describing board connections between device pins.
+#[derive(Clone, Debug)]
+pub struct Netlist {
+    nets: Vec<Net>,
+}
+
+impl Netlist {
+    /// Load a netlist from the provided path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse a netlist description.
+    pub fn parse(src: &str) -> Result<Self> {
+        let mut nets = Vec::new();
+        for (lineno, line) in src.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let err = || Error::Netlist(format!("line {}: expected NET: PIN...", lineno + 1));
+            let (name, rest) = line.split_once(':').ok_or_else(err)?;
+            let mut net = Net { name: name.trim().to_string(), pins: Vec::new(), tie: None };
+            for word in rest.split_whitespace() {
+                let tie = match word.to_ascii_uppercase().as_str() {
+                    "PULLUP" => Some(Tie::PullUp),
+                    "PULLDOWN" => Some(Tie::PullDown),
+                    "VCC" => Some(Tie::Vcc),
+                    "GND" => Some(Tie::Gnd),
+                    _ => None,
+                };
+                match tie {
+                    Some(_) if net.tie.is_some() => {
+                        return Err(Error::Netlist(format!(
+                            "line {}: net {} has multiple rail connections", lineno + 1, net.name)));
+                    },
+                    Some(tie) => net.tie = Some(tie),
+                    None => net.pins.push(word.to_ascii_uppercase()),
+                }
+            }
+            if net.name.is_empty() || net.pins.is_empty() {
+                return Err(err());
+            }
+            nets.push(net);
+        }
+        Ok(Netlist { nets })
+    }
+
+    /// All nets in the netlist.
+    pub fn nets(&self) -> &[Net] {
+        &self.nets
+    }
+
+    /// Run the interconnect test, returning all faults found.
+    ///
+    /// All pins are released when the test completes.
+    pub fn test<T: Tap>(&self, bscan: &mut BoundaryScan, x7: &mut X7<T>) -> Result<Vec<Fault>> {
+        let result = self.test_inner(bscan, x7);
+        bscan.release(x7)?;
+        result
+    }
+
+    fn test_inner<T: Tap>(&self, bscan: &mut BoundaryScan, x7: &mut X7<T>) -> Result<Vec<Fault>> {
+        // Resolve port names to package pin names, checking all pins exist.
+        let mut nets = self.nets.clone();
+        for net in nets.iter_mut() {
+            for pin in net.pins.iter_mut() {
+                *pin = bscan.pin(pin)?.name.clone();
+            }
+        }
+
+        // Find a driver for each drivable net.
+        let mut drivers: Vec<Option<String>> = Vec::new();
+        for net in &nets {
+            let mut driver = None;
+            for pin in &net.pins {
+                let pin = bscan.pin(pin)?;
+                if driver.is_none() && pin.output.is_some() && pin.control.is_some() {
+                    driver = Some(pin.name.clone());
+                }
+            }
+            if net.tie.is_none_or(|t| t.drivable()) && driver.is_none() {
+                log::warn!("Net {} has no drivable pin and will only be checked for shorts",
+                           net.name);
+            }
+            drivers.push(driver.filter(|_| net.tie.is_none_or(|t| t.drivable())));
+        }
+
+        let mut faults = BTreeSet::new();
+
+        // With every net released, check nets tied to rails read their idle value.
+        for net in &nets {
+            for pin in &net.pins {
+                bscan.set(pin, PinDrive::HighZ).or_else(ignore_undriveable)?;
+            }
+        }
+        let states = bscan.apply(x7)?;
+        for net in &nets {
+            let Some(tie) = net.tie else { continue };
+            for pin in &net.pins {
+                if states.get(pin).is_some_and(|s| *s != tie.idle_value()) {
+                    faults.insert(Fault::Tie { net: net.name.clone(), pin: pin.clone() });
+                }
+            }
+        }
+
+        // Walking ones then walking zeros.
+        for value in [true, false] {
+            for (idx, net) in nets.iter().enumerate() {
+                let Some(driver) = &drivers[idx] else { continue };
+                for (other, other_driver) in drivers.iter().enumerate() {
+                    if let Some(other_driver) = other_driver {
+                        let v = if other == idx { value } else { !value };
+                        bscan.set(other_driver, if v { PinDrive::High } else { PinDrive::Low })?;
+                    }
+                }
+                let states = bscan.apply(x7)?;
+                log::debug!("Driving net {} to {}: {:?}", net.name, value as u8, states);
+
+                if states.get(driver).is_some_and(|s| *s != value) {
+                    faults.insert(Fault::Stuck { net: net.name.clone(), value });
+                } else {
+                    for pin in &net.pins {
+                        if states.get(pin).is_some_and(|s| *s != value) {
+                            faults.insert(Fault::Open { net: net.name.clone(), pin: pin.clone() });
+                        }
+                    }
+                }
+
+                // Any other net which follows this one is shorted to it,
+                // unless it has no driver and its idle value matches.
+                for (other_idx, other) in nets.iter().enumerate() {
+                    if other_idx == idx {
+                        continue;
+                    }
+                    let expected = match (&drivers[other_idx], other.tie) {
+                        (Some(_), _) => !value,
+                        (None, Some(tie)) => tie.idle_value(),
+                        (None, None) => continue,
+                    };
+                    if expected == value {
+                        continue;
+                    }
+                    if other.pins.iter().any(|p| states.get(p).is_some_and(|s| *s == value)) {
+                        let (a, b) = if idx < other_idx { (net, other) } else { (other, net) };
+                        faults.insert(Fault::Short { net: a.name.clone(), other: b.name.clone() });
+                    }
+                }
+            }
+        }
+
+        Ok(faults.into_iter().collect())
+    }
+}
+
+/// Pins without an output driver are already high impedance.
+fn ignore_undriveable(err: Error) -> Result<()> {
+    match err {
+        Error::PinNotDriveable(_) => Ok(()),
+        err => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{X7IDCODE, mock::MockTap};
+
+    /// Pins A1, A2, B1, and B2, each with input, output, and control cells,
+    /// with all outputs disabled by default.
+    const MAP: &str = "x7dap-bsr 1
+        entity XC7A35T_CSG324
+        package CSG324
+        safe 12 429
+        pin A1 IO_A1 0 1 2:1
+        pin A2 IO_A2 3 4 5:1
+        pin B1 IO_B1 6 7 8:1
+        pin B2 IO_B2 9 10 11:1
+    ";
+
+    /// Boundary register captured with pins A1, A2, B1, and B2 at `states`.
+    fn capture(states: [bool; 4]) -> Vec<bool> {
+        states.iter().flat_map(|s| [*s, false, false]).collect()
+    }
+
+    /// Run the interconnect test for `netlist` with the pin states captured by each `apply`.
+    fn run(netlist: &str, captures: &[[bool; 4]]) -> Vec<Fault> {
+        let mut tap = MockTap::new();
+        for states in captures {
+            // Each apply runs EXTEST twice and decodes the second capture.
+            tap.push_dr(capture([false; 4]));
+            tap.push_dr(capture(*states));
+        }
+        let mut x7 = X7::new(tap, X7IDCODE::X7A35T);
+        let mut bscan = BoundaryScan::from_compact(MAP).unwrap();
+        let faults = Netlist::parse(netlist).unwrap().test(&mut bscan, &mut x7).unwrap();
+        assert_eq!(x7.tap().pending_responses(), 0);
+        faults
+    }
+
+    #[test]
+    fn parse() {
+        let netlist = Netlist::parse("
+            # Comment
+            LED0: a1 B2   # trailing comment
+            SDA: C3 D4 pullup
+            RAIL: E5 GND
+        ").unwrap();
+        let nets = netlist.nets();
+        assert_eq!(nets.len(), 3);
+        assert_eq!(nets[0].name, "LED0");
+        assert_eq!(nets[0].pins, ["A1", "B2"]);
+        assert_eq!(nets[0].tie, None);
+        assert_eq!(nets[1].tie, Some(Tie::PullUp));
+        assert_eq!(nets[2].pins, ["E5"]);
+        assert_eq!(nets[2].tie, Some(Tie::Gnd));
+
+        for bad in ["N1: A1 PULLUP GND", "N1: PULLUP", "N1 A1", ": A1"] {
+            assert!(matches!(Netlist::parse(bad), Err(Error::Netlist(_))), "{bad}");
+        }
+    }
+
+    #[test]
+    fn good_board() {
+        let captures = [
+            [false; 4],
+            [true, true, false, false],
+            [false, false, true, true],
+            [false, false, true, true],
+            [true, true, false, false],
+        ];
+        assert_eq!(run("N1: A1 A2\nN2: B1 B2", &captures), []);
+    }
+
+    #[test]
+    fn open() {
+        // A2 always reads low.
+        let captures = [[false; 4], [true, false, false, false], [false; 4]];
+        assert_eq!(run("N1: A1 A2", &captures), [
+            Fault::Open { net: "N1".to_string(), pin: "A2".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn short() {
+        // N1 and N2 are wired-OR, so either driving high pulls both high.
+        let captures = [
+            [false; 4],
+            [true; 4],
+            [true; 4],
+            [true; 4],
+            [true; 4],
+        ];
+        assert_eq!(run("N1: A1 A2\nN2: B1 B2", &captures), [
+            Fault::Short { net: "N1".to_string(), other: "N2".to_string() },
+            Fault::Stuck { net: "N1".to_string(), value: false },
+            Fault::Stuck { net: "N2".to_string(), value: false },
+        ]);
+    }
+
+    #[test]
+    fn tie() {
+        // N2's pull-up is missing, so it reads low while released.
+        let captures = [
+            [false; 4],
+            [true, true, false, false],
+            [false, false, true, true],
+            [false, false, true, true],
+            [true, true, false, false],
+        ];
+        assert_eq!(run("N1: A1 A2\nN2: B1 B2 PULLUP", &captures), [
+            Fault::Tie { net: "N2".to_string(), pin: "B1".to_string() },
+            Fault::Tie { net: "N2".to_string(), pin: "B2".to_string() },
+        ]);
+    }
+}
@@ -5,11 +5,27 @@
 use std::{fmt, time::Duration, convert::{From, TryFrom}, path::Path, fs::File, io::Read};
 use num_enum::TryFromPrimitive;
 use indicatif::{ProgressBar, ProgressStyle};
-use jtagdap::jtag::{IDCODE, JTAGTAP, JTAGChain, Error as JTAGError};
+use jtagdap::jtag::{IDCODE, JTAG, JTAGTAP, JTAGChain, Error as JTAGError};
 use jtagdap::bitvec::{self, bytes_to_bits, bits_to_bytes, Error as BitvecError};
+use svf::SvfWriter;
+use tap::Tap;
 
 pub use jtagdap;
 
+pub mod axi;
+pub mod bscan;
+pub mod bsdl;
+pub mod interconnect;
+pub mod metrics;
+pub mod mock;
+pub mod sim;
+pub mod svf;
+pub mod tap;
+pub mod taps;
+pub mod uart;
+pub mod xsvf;
+pub mod xvc;
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("Device status register in incorrect state.")]
@@ -23,6 +39,52 @@ pub enum Error {
     IncompatibleIdcode { bitstream: u32, jtag: u32 },
     #[error("Could not remove VERIFY_IDCODE because parsing the bitstream failed")]
     RemoveIdcodeNoMetadata,
+    #[error("Could not parse bitstream: {0}")]
+    BitstreamParse(String),
+    #[error("Unknown device {0}.")]
+    UnknownDevice(String),
+    #[error("BSDL parse error: {0}")]
+    BsdlParse(String),
+    #[error("No boundary-scan map found for {0}, specify one using --bsdl.")]
+    BsdlNotFound(String),
+    #[error("BSDL file does not match the detected device IDCODE 0x{0:08X}.")]
+    BsdlIdcodeMismatch(u32),
+    #[error("Boundary-scan map for {entity} does not match the detected device {device}.")]
+    BsrDeviceMismatch { entity: String, device: &'static str },
+    #[error("Package {0} not found.")]
+    UnknownPackage(String),
+    #[error("Pin {0} not found.")]
+    UnknownPin(String),
+    #[error("Pin {0} cannot be driven.")]
+    PinNotDriveable(String),
+    #[error("Pin {0} has no input cell.")]
+    PinNotSampleable(String),
+    #[error("Invalid pin value {0}, expected 0, 1, or Z.")]
+    InvalidPinValue(String),
+    #[error("Netlist error: {0}")]
+    Netlist(String),
+    #[error("Invalid USER register {0}, expected 1 to 4.")]
+    InvalidUserRegister(u8),
+    #[error("USER data is {0} bits but only {1} bits are shifted.")]
+    UserDataTooLong(usize, usize),
+    #[error("SLR {0} does not exist, the device has {1} SLRs.")]
+    InvalidSlr(usize, usize),
+    #[error("{0} has GTZ transceivers on its JTAG chain, which are not supported.")]
+    GtzUnsupported(&'static str),
+    #[error("{0} is not supported on {1} devices.")]
+    UnsupportedCommand(String, &'static str),
+    #[error("AXI address 0x{0:08X} is not word aligned.")]
+    AxiUnaligned(u32),
+    #[error("AXI access to 0x{0:08X} failed with response {1}.")]
+    AxiResponse(u32, u8),
+    #[error("AXI access to 0x{0:08X} timed out, check the bridge is present.")]
+    AxiTimeout(u32),
+    #[error("XVC protocol error: {0}")]
+    Xvc(String),
+    #[error("SVF parse error: {0}")]
+    SvfParse(String),
+    #[error("SVF command {0} is not supported.")]
+    SvfUnsupported(String),
     #[error("SPI Flash error")]
     SPIFlash(#[from] spi_flash::Error),
     #[error("JTAG error")]
@@ -37,11 +99,23 @@ pub enum Error {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-/// IDCODEs for all X7 device types.
+/// IDCODEs of supported Xilinx devices.
+///
+/// Despite the name, this covers 7-series, UltraScale, and UltraScale+ devices,
+/// as well as Spartan-6 and Virtex-6 devices; use `X7IDCODE::family` to find a
+/// device's `Family`.
 ///
 /// IDCODEs are the same between C/A/Q part numbers (e.g. XC7Z030, XA7Z030, XQ7Z030).
+/// UltraScale and UltraScale+ devices are named without the family digit,
+/// e.g. XKU5P for XCKU5P, and Spartan-6 and Virtex-6 devices with their
+/// family digit, e.g. X6SLX9 for XC6SLX9.
 ///
 /// Note first byte is the revision which may vary and so is 0 here.
+///
+/// Each device has a single IDCODE here. Alternative IDCODEs reported for
+/// some XC7VX1140T steppings, Spartan-7 devices, and XC7A12T/XC7A25T
+/// packages are not included, as they could not be confirmed against
+/// Xilinx BSDL files; such devices are reported as unknown until added.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, TryFromPrimitive)]
 #[allow(non_camel_case_types)]
 #[repr(u32)]
@@ -67,7 +141,8 @@ pub enum X7IDCODE {
     X7K410T     = 0x03656093,
     X7K420T     = 0x03752093,
     X7K480T     = 0x03751093,
-    X7V575T     = 0x03671093,
+    X7V585T     = 0x03671093,
+    X7V2000T    = 0x036B3093,
     X7VX330T    = 0x03667093,
     X7VX415T    = 0x03682093,
     X7VX485T    = 0x03687093,
@@ -87,6 +162,52 @@ pub enum X7IDCODE {
     X7Z035      = 0x03732093,
     X7Z045      = 0x03731093,
     X7Z100      = 0x03736093,
+    XKU035      = 0x03823093,
+    XKU040      = 0x03822093,
+    XKU060      = 0x03919093,
+    XKU095      = 0x03844093,
+    XVU065      = 0x03939093,
+    XVU080      = 0x03843093,
+    XVU095      = 0x03842093,
+    XKU3P       = 0x04A63093,
+    XKU5P       = 0x04A62093,
+    XKU9P       = 0x0484A093,
+    XKU11P      = 0x04A4E093,
+    XKU13P      = 0x04A52093,
+    XKU15P      = 0x04A56093,
+    XAU10P      = 0x04AC4093,
+    XAU15P      = 0x04AC2093,
+    XVU3P       = 0x04B39093,
+    X6SLX4      = 0x04000093,
+    X6SLX9      = 0x04001093,
+    X6SLX16     = 0x04002093,
+    X6SLX25     = 0x04004093,
+    X6SLX25T    = 0x04024093,
+    X6SLX45     = 0x04008093,
+    X6SLX45T    = 0x04028093,
+    X6SLX75     = 0x0400E093,
+    X6SLX75T    = 0x0402E093,
+    X6SLX100    = 0x04011093,
+    X6SLX100T   = 0x04031093,
+    X6SLX150    = 0x0401D093,
+    X6SLX150T   = 0x0403D093,
+    X6VLX75T    = 0x04244093,
+    X6VLX130T   = 0x0424A093,
+    X6VLX195T   = 0x0424C093,
+    X6VLX240T   = 0x04250093,
+    X6VLX365T   = 0x04252093,
+    X6VLX550T   = 0x04256093,
+    X6VLX760    = 0x0423A093,
+    X6VSX315T   = 0x04286093,
+    X6VSX475T   = 0x04288093,
+    X6VHX250T   = 0x042A2093,
+    X6VHX255T   = 0x042A4093,
+    X6VHX380T   = 0x042A8093,
+    X6VHX565T   = 0x042AC093,
+    X6VCX75T    = 0x042C4093,
+    X6VCX130T   = 0x042CA093,
+    X6VCX195T   = 0x042CC093,
+    X6VCX240T   = 0x042D0093,
 }
 
 impl From<X7IDCODE> for IDCODE {
@@ -110,8 +231,26 @@ impl X7IDCODE {
         Self::try_from_idcode(IDCODE(idcode))
     }
 
+    /// Previous name for `X7V585T`.
+    #[deprecated(note = "use X7IDCODE::X7V585T")]
+    pub const X7V575T: X7IDCODE = X7IDCODE::X7V585T;
+
+    /// Look up a device by name.
+    ///
+    /// Accepts names such as "X7A35T", and marketing names such as "XC7A35T",
+    /// "xa7z020", "XCKU5P", "XC6SLX9", or "XQ7K325T-2RF900I" including any
+    /// speed grade and package.
     pub fn try_from_name(name: &str) -> Option<Self> {
-        match name.to_ascii_uppercase().as_str() {
+        let name = name.to_ascii_uppercase();
+        let name = name.split('-').next().unwrap_or_default();
+        Self::try_from_short_name(name).or_else(|| {
+            let rest = ["XC", "XA", "XQ"].iter().find_map(|p| name.strip_prefix(p))?;
+            Self::try_from_short_name(&format!("X{rest}"))
+        })
+    }
+
+    fn try_from_short_name(name: &str) -> Option<Self> {
+        match name {
             "X7S6" => Some(X7IDCODE::X7S6),
             "X7S15" => Some(X7IDCODE::X7S15),
             "X7S25" => Some(X7IDCODE::X7S25),
@@ -133,7 +272,8 @@ impl X7IDCODE {
             "X7K410T" => Some(X7IDCODE::X7K410T),
             "X7K420T" => Some(X7IDCODE::X7K420T),
             "X7K480T" => Some(X7IDCODE::X7K480T),
-            "X7V575T" => Some(X7IDCODE::X7V575T),
+            "X7V585T" | "X7V575T" => Some(X7IDCODE::X7V585T),
+            "X7V2000T" => Some(X7IDCODE::X7V2000T),
             "X7VX330T" => Some(X7IDCODE::X7VX330T),
             "X7VX415T" => Some(X7IDCODE::X7VX415T),
             "X7VX485T" => Some(X7IDCODE::X7VX485T),
@@ -153,6 +293,52 @@ impl X7IDCODE {
             "X7Z035" => Some(X7IDCODE::X7Z035),
             "X7Z045" => Some(X7IDCODE::X7Z045),
             "X7Z100" => Some(X7IDCODE::X7Z100),
+            "XKU035" => Some(X7IDCODE::XKU035),
+            "XKU040" => Some(X7IDCODE::XKU040),
+            "XKU060" => Some(X7IDCODE::XKU060),
+            "XKU095" => Some(X7IDCODE::XKU095),
+            "XVU065" => Some(X7IDCODE::XVU065),
+            "XVU080" => Some(X7IDCODE::XVU080),
+            "XVU095" => Some(X7IDCODE::XVU095),
+            "XKU3P" => Some(X7IDCODE::XKU3P),
+            "XKU5P" => Some(X7IDCODE::XKU5P),
+            "XKU9P" => Some(X7IDCODE::XKU9P),
+            "XKU11P" => Some(X7IDCODE::XKU11P),
+            "XKU13P" => Some(X7IDCODE::XKU13P),
+            "XKU15P" => Some(X7IDCODE::XKU15P),
+            "XAU10P" => Some(X7IDCODE::XAU10P),
+            "XAU15P" => Some(X7IDCODE::XAU15P),
+            "XVU3P" => Some(X7IDCODE::XVU3P),
+            "X6SLX4" => Some(X7IDCODE::X6SLX4),
+            "X6SLX9" => Some(X7IDCODE::X6SLX9),
+            "X6SLX16" => Some(X7IDCODE::X6SLX16),
+            "X6SLX25" => Some(X7IDCODE::X6SLX25),
+            "X6SLX25T" => Some(X7IDCODE::X6SLX25T),
+            "X6SLX45" => Some(X7IDCODE::X6SLX45),
+            "X6SLX45T" => Some(X7IDCODE::X6SLX45T),
+            "X6SLX75" => Some(X7IDCODE::X6SLX75),
+            "X6SLX75T" => Some(X7IDCODE::X6SLX75T),
+            "X6SLX100" => Some(X7IDCODE::X6SLX100),
+            "X6SLX100T" => Some(X7IDCODE::X6SLX100T),
+            "X6SLX150" => Some(X7IDCODE::X6SLX150),
+            "X6SLX150T" => Some(X7IDCODE::X6SLX150T),
+            "X6VLX75T" => Some(X7IDCODE::X6VLX75T),
+            "X6VLX130T" => Some(X7IDCODE::X6VLX130T),
+            "X6VLX195T" => Some(X7IDCODE::X6VLX195T),
+            "X6VLX240T" => Some(X7IDCODE::X6VLX240T),
+            "X6VLX365T" => Some(X7IDCODE::X6VLX365T),
+            "X6VLX550T" => Some(X7IDCODE::X6VLX550T),
+            "X6VLX760" => Some(X7IDCODE::X6VLX760),
+            "X6VSX315T" => Some(X7IDCODE::X6VSX315T),
+            "X6VSX475T" => Some(X7IDCODE::X6VSX475T),
+            "X6VHX250T" => Some(X7IDCODE::X6VHX250T),
+            "X6VHX255T" => Some(X7IDCODE::X6VHX255T),
+            "X6VHX380T" => Some(X7IDCODE::X6VHX380T),
+            "X6VHX565T" => Some(X7IDCODE::X6VHX565T),
+            "X6VCX75T" => Some(X7IDCODE::X6VCX75T),
+            "X6VCX130T" => Some(X7IDCODE::X6VCX130T),
+            "X6VCX195T" => Some(X7IDCODE::X6VCX195T),
+            "X6VCX240T" => Some(X7IDCODE::X6VCX240T),
             _ => None,
         }
     }
@@ -180,7 +366,8 @@ impl X7IDCODE {
             X7IDCODE::X7K410T => "X7K410T",
             X7IDCODE::X7K420T => "X7K420T",
             X7IDCODE::X7K480T => "X7K480T",
-            X7IDCODE::X7V575T => "X7V575T",
+            X7IDCODE::X7V585T => "X7V585T",
+            X7IDCODE::X7V2000T => "X7V2000T",
             X7IDCODE::X7VX330T => "X7VX330T",
             X7IDCODE::X7VX415T => "X7VX415T",
             X7IDCODE::X7VX485T => "X7VX485T",
@@ -200,6 +387,52 @@ impl X7IDCODE {
             X7IDCODE::X7Z035 => "X7Z035",
             X7IDCODE::X7Z045 => "X7Z045",
             X7IDCODE::X7Z100 => "X7Z100",
+            X7IDCODE::XKU035 => "XKU035",
+            X7IDCODE::XKU040 => "XKU040",
+            X7IDCODE::XKU060 => "XKU060",
+            X7IDCODE::XKU095 => "XKU095",
+            X7IDCODE::XVU065 => "XVU065",
+            X7IDCODE::XVU080 => "XVU080",
+            X7IDCODE::XVU095 => "XVU095",
+            X7IDCODE::XKU3P => "XKU3P",
+            X7IDCODE::XKU5P => "XKU5P",
+            X7IDCODE::XKU9P => "XKU9P",
+            X7IDCODE::XKU11P => "XKU11P",
+            X7IDCODE::XKU13P => "XKU13P",
+            X7IDCODE::XKU15P => "XKU15P",
+            X7IDCODE::XAU10P => "XAU10P",
+            X7IDCODE::XAU15P => "XAU15P",
+            X7IDCODE::XVU3P => "XVU3P",
+            X7IDCODE::X6SLX4 => "X6SLX4",
+            X7IDCODE::X6SLX9 => "X6SLX9",
+            X7IDCODE::X6SLX16 => "X6SLX16",
+            X7IDCODE::X6SLX25 => "X6SLX25",
+            X7IDCODE::X6SLX25T => "X6SLX25T",
+            X7IDCODE::X6SLX45 => "X6SLX45",
+            X7IDCODE::X6SLX45T => "X6SLX45T",
+            X7IDCODE::X6SLX75 => "X6SLX75",
+            X7IDCODE::X6SLX75T => "X6SLX75T",
+            X7IDCODE::X6SLX100 => "X6SLX100",
+            X7IDCODE::X6SLX100T => "X6SLX100T",
+            X7IDCODE::X6SLX150 => "X6SLX150",
+            X7IDCODE::X6SLX150T => "X6SLX150T",
+            X7IDCODE::X6VLX75T => "X6VLX75T",
+            X7IDCODE::X6VLX130T => "X6VLX130T",
+            X7IDCODE::X6VLX195T => "X6VLX195T",
+            X7IDCODE::X6VLX240T => "X6VLX240T",
+            X7IDCODE::X6VLX365T => "X6VLX365T",
+            X7IDCODE::X6VLX550T => "X6VLX550T",
+            X7IDCODE::X6VLX760 => "X6VLX760",
+            X7IDCODE::X6VSX315T => "X6VSX315T",
+            X7IDCODE::X6VSX475T => "X6VSX475T",
+            X7IDCODE::X6VHX250T => "X6VHX250T",
+            X7IDCODE::X6VHX255T => "X6VHX255T",
+            X7IDCODE::X6VHX380T => "X6VHX380T",
+            X7IDCODE::X6VHX565T => "X6VHX565T",
+            X7IDCODE::X6VCX75T => "X6VCX75T",
+            X7IDCODE::X6VCX130T => "X6VCX130T",
+            X7IDCODE::X6VCX195T => "X6VCX195T",
+            X7IDCODE::X6VCX240T => "X6VCX240T",
         }
     }
 
@@ -217,17 +450,295 @@ impl X7IDCODE {
     }
 
     pub fn is_zynq7000(&self) -> bool {
-        match *self {
+        matches!(
+            *self,
             X7IDCODE::X7Z007S | X7IDCODE::X7Z012S | X7IDCODE::X7Z014S | X7IDCODE::X7Z010 |
             X7IDCODE::X7Z015  | X7IDCODE::X7Z020  | X7IDCODE::X7Z030  | X7IDCODE::X7Z035 |
-            X7IDCODE::X7Z045  | X7IDCODE::X7Z100 => true,
+            X7IDCODE::X7Z045  | X7IDCODE::X7Z100
+        )
+    }
+
+    /// Device family.
+    pub fn family(&self) -> Family {
+        match (*self as u32) >> 20 {
+            0x040 => Family::Spartan6,
+            0x042 => Family::Virtex6,
+            0x038 | 0x039 => Family::UltraScale,
+            0x047 | 0x048 | 0x04A | 0x04B => Family::UltraScalePlus,
+            _ => Family::Series7,
+        }
+    }
+
+    /// Number of super logic regions in this device.
+    pub fn slr_count(&self) -> usize {
+        match self {
+            X7IDCODE::X7V2000T | X7IDCODE::X7VX1140T => 4,
+            X7IDCODE::X7VH870T => 3,
+            X7IDCODE::X7VH580T => 2,
+            _ => 1,
+        }
+    }
+
+    /// Index of the master SLR, which receives the bitstream.
+    pub fn master_slr(&self) -> usize {
+        match self {
+            X7IDCODE::X7V2000T | X7IDCODE::X7VX1140T => 1,
+            _ => 0,
+        }
+    }
+
+    /// Whether this device has GTZ transceivers with their own TAPs,
+    /// as on Virtex-7 HT devices.
+    ///
+    /// The GTZ TAPs are not supported, so all commands to these devices fail.
+    pub fn has_gtz(&self) -> bool {
+        matches!(self, X7IDCODE::X7VH580T | X7IDCODE::X7VH870T)
+    }
+
+    /// Total IR length of the SLRs of this device, `Family::ir_length` bits per SLR.
+    ///
+    /// This does not include the IRs of any GTZ TAPs, see `has_gtz`.
+    pub fn ir_length(&self) -> usize {
+        self.family().ir_length() * self.slr_count()
+    }
+
+    /// Whether `idcodes` is the JTAG chain of a single Zynq-7000 device,
+    /// with the PL TAP nearest TDO followed by the ARM DAP.
+    pub fn is_zynq7000_chain(idcodes: &[Option<IDCODE>]) -> bool {
+        match idcodes {
+            [Some(pl), Some(dap)] => {
+                Self::try_from_idcode(*pl).is_some_and(|id| id.is_zynq7000())
+                    && dap.0 == ZYNQ7000_ARM_DAP_IDCODE
+            },
             _ => false,
         }
     }
 }
 
+/// IDCODE of the ARM DAP in Zynq-7000 devices.
+pub const ZYNQ7000_ARM_DAP_IDCODE: u32 = 0x4BA00477;
+
+/// IR lengths of a Zynq-7000 JTAG chain, PL TAP first.
+pub const ZYNQ7000_IR_LENGTHS: [usize; 2] = [6, 4];
+
+/// Scan the JTAG chain to detect all available TAPs.
+///
+/// If `ir_lengths` is not given and the IR lengths are ambiguous, the
+/// IDCODEs are read and the scan is retried with `ZYNQ7000_IR_LENGTHS` if
+/// the chain is a single Zynq-7000, or otherwise with IR lengths from the
+/// `taps` database.
+pub fn scan_chain(jtag: &mut JTAG, ir_lengths: Option<&[usize]>) -> Result<JTAGChain> {
+    match jtag.scan(ir_lengths) {
+        Err(JTAGError::InvalidIR) if ir_lengths.is_none() => {
+            let idcodes = taps::read_idcodes(jtag)?;
+            let irlens = if X7IDCODE::is_zynq7000_chain(&idcodes) {
+                log::info!("Detected Zynq-7000, using IR lengths {:?}", ZYNQ7000_IR_LENGTHS);
+                ZYNQ7000_IR_LENGTHS.to_vec()
+            } else if let Some(irlens) = taps::ir_lengths(&idcodes) {
+                log::info!("Using IR lengths of known TAPs: {:?}", irlens);
+                irlens
+            } else {
+                log::error!("IR lengths of some TAPs are not known and must be specified.");
+                return Err(JTAGError::InvalidIR.into());
+            };
+            Ok(jtag.scan(Some(&irlens))?)
+        },
+        result => Ok(result?),
+    }
+}
+
+/// Device family.
+///
+/// 7-series, UltraScale, and UltraScale+ devices share their JTAG instructions
+/// and configuration packets; they differ in IDCODEs, frame size, DNA length,
+/// and the system monitor, which is an XADC on 7-series devices and a SYSMONE1
+/// or SYSMONE4 on UltraScale and UltraScale+ devices.
+///
+/// Spartan-6 and Virtex-6 devices use different instruction opcodes, see
+/// `Command::opcode`, and read their DNA with ISC_DNA. Spartan-6 also
+/// uses 16-bit configuration words with its own STAT layout and has no
+/// system monitor.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Family {
+    Spartan6,
+    Virtex6,
+    #[default]
+    Series7,
+    UltraScale,
+    UltraScalePlus,
+}
+
+impl Family {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Family::Spartan6 => "Spartan-6",
+            Family::Virtex6 => "Virtex-6",
+            Family::Series7 => "7-series",
+            Family::UltraScale => "UltraScale",
+            Family::UltraScalePlus => "UltraScale+",
+        }
+    }
+
+    /// Length of the instruction register of one SLR, in bits.
+    pub fn ir_length(&self) -> usize {
+        match self {
+            Family::Virtex6 => 10,
+            _ => 6,
+        }
+    }
+
+    /// Size of a configuration word, in bits.
+    pub fn word_bits(&self) -> usize {
+        match self {
+            Family::Spartan6 => 16,
+            _ => 32,
+        }
+    }
+
+    /// Number of configuration words in one configuration frame.
+    pub fn frame_words(&self) -> usize {
+        match self {
+            Family::Spartan6 => 65,
+            Family::Virtex6 => 81,
+            Family::Series7 => 101,
+            Family::UltraScale => 123,
+            Family::UltraScalePlus => 93,
+        }
+    }
+
+    /// Length of the device DNA, in bits.
+    pub fn dna_bits(&self) -> usize {
+        match self {
+            Family::Spartan6 | Family::Virtex6 => 57,
+            Family::Series7 => 64,
+            Family::UltraScale | Family::UltraScalePlus => 96,
+        }
+    }
+
+    /// Convert a raw system monitor temperature reading to degrees Celsius.
+    ///
+    /// The Virtex-6 system monitor has the same transfer function as the
+    /// 7-series XADC, at 10 bits rather than 12. Spartan-6 devices have no
+    /// system monitor, and use the 7-series conversion.
+    pub fn temperature(&self, raw: u16) -> f32 {
+        match self {
+            Family::Spartan6 | Family::Virtex6 | Family::Series7 =>
+                ((raw >> 4) as f32 * 503.975)/4096.0 - 273.15,
+            Family::UltraScale => (raw as f32 * 502.9098)/65536.0 - 273.8195,
+            Family::UltraScalePlus => (raw as f32 * 509.314)/65536.0 - 280.2309,
+        }
+    }
+}
+
+/// A 7-series device along with its silicon revision.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DeviceId {
+    pub device: X7IDCODE,
+    /// Revision, from the version field of the IDCODE.
+    pub revision: u8,
+}
+
+impl DeviceId {
+    pub fn new(device: X7IDCODE, revision: u8) -> Self {
+        DeviceId { device, revision }
+    }
+
+    pub fn try_from_idcode(idcode: IDCODE) -> Option<Self> {
+        X7IDCODE::try_from_idcode(idcode).map(|device| Self::new(device, idcode.version()))
+    }
+
+    pub fn try_from_u32(idcode: u32) -> Option<Self> {
+        Self::try_from_idcode(IDCODE(idcode))
+    }
+
+    /// Full IDCODE including the revision.
+    pub fn idcode(&self) -> u32 {
+        (self.device as u32) | (self.revision as u32) << 28
+    }
+
+    /// Returns whether a bitstream for `bitstream` may be loaded into this device.
+    ///
+    /// Bitstreams normally have a revision of 0 and load into any revision.
+    /// If `warn_revision` is set, a warning is logged when the bitstream
+    /// specifies a different revision to the device.
+    pub fn compatible(&self, bitstream: DeviceId, warn_revision: bool) -> bool {
+        if !self.device.compatible(bitstream.device) {
+            return false;
+        }
+        if warn_revision && bitstream.revision != 0 && bitstream.revision != self.revision {
+            log::warn!("Bitstream targets revision {} but device {} is revision {}",
+                       bitstream.revision, self.device.name(), self.revision);
+        }
+        true
+    }
+}
+
+impl From<X7IDCODE> for DeviceId {
+    fn from(device: X7IDCODE) -> Self {
+        DeviceId::new(device, 0)
+    }
+}
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} rev {}", self.device.name(), self.revision)
+    }
+}
+
+/// Which devices a bitstream may be loaded into, for the bitstream IDCODE check.
+///
+/// By default a bitstream may only be loaded into the device it was built
+/// for. Groups of devices can be declared compatible, in which case the
+/// bitstream's IDCODE is changed to match the device before programming.
+#[derive(Clone, Debug, Default)]
+pub struct Compatibility {
+    groups: Vec<Vec<X7IDCODE>>,
+}
+
+impl Compatibility {
+    /// Devices which are built on the same die, so share a bitstream format.
+    pub const SAME_DIE: &[&[X7IDCODE]] = &[
+        &[X7IDCODE::X7A15T, X7IDCODE::X7A35T, X7IDCODE::X7A50T],
+        &[X7IDCODE::X7A75T, X7IDCODE::X7A100T],
+        &[X7IDCODE::X7Z007S, X7IDCODE::X7Z010],
+        &[X7IDCODE::X7Z012S, X7IDCODE::X7Z015],
+        &[X7IDCODE::X7Z014S, X7IDCODE::X7Z020],
+    ];
+
+    /// Only allow bitstreams built for the same device.
+    pub fn strict() -> Self {
+        Self::default()
+    }
+
+    /// Allow bitstreams built for any device on the same die.
+    pub fn same_die() -> Self {
+        Self::SAME_DIE.iter().fold(Self::strict(), |c, group| c.with_group(group))
+    }
+
+    /// Declare every device in `group` compatible with every other.
+    pub fn with_group(mut self, group: &[X7IDCODE]) -> Self {
+        self.groups.push(group.to_vec());
+        self
+    }
+
+    /// Parse a comma-separated list of device names into a group.
+    pub fn parse_group(group: &str) -> Result<Vec<X7IDCODE>> {
+        group.split(',').map(str::trim).filter(|name| !name.is_empty()).map(|name| {
+            X7IDCODE::try_from_name(name).ok_or_else(|| Error::UnknownDevice(name.to_string()))
+        }).collect()
+    }
+
+    /// Returns whether a bitstream built for `bitstream` may be loaded into `device`.
+    pub fn compatible(&self, device: X7IDCODE, bitstream: X7IDCODE) -> bool {
+        device.compatible(bitstream) || self.groups.iter().any(|group| {
+            group.contains(&device) && group.contains(&bitstream)
+        })
+    }
+}
+
 pub fn check_tap_idx(chain: &JTAGChain, index: usize) -> Option<X7IDCODE> {
-    match chain.idcodes().iter().nth(index) {
+    match chain.idcodes().get(index) {
         Some(Some(idcode)) => X7IDCODE::try_from_idcode(*idcode),
         _ => None,
     }
@@ -256,11 +767,14 @@ pub fn auto_tap_idx(chain: &JTAGChain) -> Option<(usize, X7IDCODE)> {
     }
 }
 
-/// 7-series JTAG instructions.
+/// JTAG instructions.
+///
+/// Discriminants are the 7-series and UltraScale opcodes, see `opcode` for
+/// the opcodes of other families.
 #[derive(Copy, Clone, Debug)]
 #[allow(unused, non_camel_case_types, clippy::upper_case_acronyms)]
 #[repr(u8)]
-enum Command {
+pub(crate) enum Command {
     EXTEST = 0b100110,
     EXTEST_PULSE = 0b111100,
     EXTEST_TRAIN = 0b1111101,
@@ -289,8 +803,53 @@ enum Command {
 }
 
 impl Command {
-    pub fn bits(&self) -> Vec<bool> {
-        jtagdap::bitvec::bytes_to_bits(&[*self as u8], 6).unwrap()
+    /// Opcode of this instruction in `family`, if it has one.
+    ///
+    /// XSC_DNA is ISC_DNA and XADC_DRP is SYSMON_DRP on Spartan-6 and Virtex-6.
+    pub fn opcode(&self, family: Family) -> Option<u16> {
+        match family {
+            Family::Series7 | Family::UltraScale | Family::UltraScalePlus => Some(*self as u16),
+            Family::Spartan6 => match self {
+                Command::EXTEST => Some(0x0F),
+                Command::SAMPLE => Some(0x01),
+                Command::USER1 => Some(0x02),
+                Command::USER2 => Some(0x03),
+                Command::USER3 => Some(0x1A),
+                Command::USER4 => Some(0x1B),
+                Command::CFG_OUT => Some(0x04),
+                Command::CFG_IN => Some(0x05),
+                Command::USERCODE => Some(0x08),
+                Command::IDCODE => Some(0x09),
+                Command::HIGHZ_IO => Some(0x0A),
+                Command::JPROGRAM => Some(0x0B),
+                Command::JSTART => Some(0x0C),
+                Command::JSHUTDOWN => Some(0x0D),
+                Command::ISC_ENABLE => Some(0x10),
+                Command::ISC_PROGRAM => Some(0x11),
+                Command::ISC_NOOP => Some(0x14),
+                Command::ISC_DISABLE => Some(0x16),
+                Command::XSC_DNA => Some(0x30),
+                Command::BYPASS => Some(0x3F),
+                _ => None,
+            },
+            // Virtex-6 sets the upper four bits of the Spartan-6 opcode, except for
+            // the instructions it moves.
+            Family::Virtex6 => match self {
+                Command::EXTEST => Some(0x3C0),
+                Command::USER3 => Some(0x3E2),
+                Command::USER4 => Some(0x3E3),
+                Command::XSC_DNA => Some(0x3D7),
+                Command::XADC_DRP => Some(0x3F7),
+                Command::BYPASS => Some(0x3FF),
+                _ => Some(0x3C0 | self.opcode(Family::Spartan6)?),
+            },
+        }
+    }
+
+    /// Opcode of this instruction in `family` as IR bits, least significant first.
+    pub fn bits(&self, family: Family) -> Option<Vec<bool>> {
+        let opcode = self.opcode(family)?;
+        Some((0..family.ir_length()).map(|i| (opcode >> i) & 1 == 1).collect())
     }
 }
 
@@ -347,21 +906,45 @@ enum XadcReg {
     Flag = 0x3f,
 }
 
+/// Units for an XADC measurement.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum XadcUnits {
+    Celsius,
+    Volts,
+}
+
+impl fmt::Display for XadcUnits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            XadcUnits::Celsius => write!(f, "°C"),
+            XadcUnits::Volts => write!(f, "V"),
+        }
+    }
+}
+
+/// Minimum, maximum, and current value of an XADC measurement.
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MinMaxNow {
     min: f32,
     max: f32,
     current: f32,
-    units: &'static str,
+    units: XadcUnits,
 }
 
 impl MinMaxNow {
     pub fn from_temperature(min: u16, max: u16, current: u16) -> Self {
+        Self::from_family_temperature(Family::Series7, min, max, current)
+    }
+
+    /// Convert temperature readings from the system monitor of a `family` device.
+    pub fn from_family_temperature(family: Family, min: u16, max: u16, current: u16) -> Self {
         Self {
-            min: ((min >> 4) as f32 * 503.975)/4096.0 - 273.15,
-            max: ((max >> 4) as f32 * 503.975)/4096.0 - 273.15,
-            current: ((current >> 4) as f32 * 503.975)/4096.0 - 273.15,
-            units: "°C",
+            min: family.temperature(min),
+            max: family.temperature(max),
+            current: family.temperature(current),
+            units: XadcUnits::Celsius,
         }
     }
 
@@ -370,9 +953,29 @@ impl MinMaxNow {
             min: ((min >> 4) as f32 * 3.0)/4096.0,
             max: ((max >> 4) as f32 * 3.0)/4096.0,
             current: ((current >> 4) as f32 * 3.0)/4096.0,
-            units: "V",
+            units: XadcUnits::Volts,
         }
     }
+
+    /// Minimum value recorded since power-up or the last reset.
+    pub fn min(&self) -> f32 {
+        self.min
+    }
+
+    /// Maximum value recorded since power-up or the last reset.
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+
+    /// Most recently converted value.
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    /// Units of all three values.
+    pub fn units(&self) -> XadcUnits {
+        self.units
+    }
 }
 
 impl fmt::Display for MinMaxNow {
@@ -390,7 +993,9 @@ impl fmt::Display for MinMaxNow {
     }
 }
 
+/// All XADC readings available over JTAG.
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct XadcReading {
     temperature: MinMaxNow,
     vccint: MinMaxNow,
@@ -405,6 +1010,63 @@ pub struct XadcReading {
     is_zynq7000: bool,
 }
 
+impl XadcReading {
+    /// Die temperature in °C.
+    pub fn temperature(&self) -> MinMaxNow {
+        self.temperature
+    }
+
+    /// VCCINT supply voltage.
+    pub fn vccint(&self) -> MinMaxNow {
+        self.vccint
+    }
+
+    /// VCCAUX supply voltage.
+    pub fn vccaux(&self) -> MinMaxNow {
+        self.vccaux
+    }
+
+    /// VCCBRAM supply voltage.
+    pub fn vccbram(&self) -> MinMaxNow {
+        self.vccbram
+    }
+
+    /// VCCPINT supply voltage, only present on Zynq-7000 devices.
+    pub fn vccpint(&self) -> Option<MinMaxNow> {
+        self.is_zynq7000.then_some(self.vccpint)
+    }
+
+    /// VCCPAUX supply voltage, only present on Zynq-7000 devices.
+    pub fn vccpaux(&self) -> Option<MinMaxNow> {
+        self.is_zynq7000.then_some(self.vccpaux)
+    }
+
+    /// VCCO_DDR supply voltage, only present on Zynq-7000 devices.
+    pub fn vccoddr(&self) -> Option<MinMaxNow> {
+        self.is_zynq7000.then_some(self.vccoddr)
+    }
+
+    /// VREFP reference voltage.
+    pub fn vrefp(&self) -> f32 {
+        self.vrefp
+    }
+
+    /// VREFN reference voltage.
+    pub fn vrefn(&self) -> f32 {
+        self.vrefn
+    }
+
+    /// Raw contents of the XADC flag register.
+    pub fn flag(&self) -> u16 {
+        self.flag
+    }
+
+    /// Whether this reading was taken from a Zynq-7000 device.
+    pub fn is_zynq7000(&self) -> bool {
+        self.is_zynq7000
+    }
+}
+
 impl fmt::Display for XadcReading {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.is_zynq7000 {
@@ -440,6 +1102,43 @@ impl fmt::Display for XadcReading {
     }
 }
 
+const DATA_PROGRESS_TPL: &str =
+    " {msg} [{bar:40.cyan/black}] {bytes}/{total_bytes} ({bytes_per_sec}; {eta_precise})";
+const DATA_FINISHED_TPL: &str =
+    " {msg} [{bar:40.green/black}] {bytes}/{total_bytes} ({bytes_per_sec}; {eta_precise})";
+const DATA_PROGRESS_CHARS: &str = "━╸━";
+
+/// Create a progress bar for programming `len` bytes, labelled with `msg`.
+pub fn program_progress_bar(len: usize, msg: String) -> ProgressBar {
+    let pb = ProgressBar::new(len as u64).with_style(
+        ProgressStyle::with_template(DATA_PROGRESS_TPL)
+            .unwrap()
+            .progress_chars(DATA_PROGRESS_CHARS));
+    pb.set_message(msg);
+    pb.set_position(0);
+    pb
+}
+
+/// Mark a progress bar from `program_progress_bar` as finished.
+pub fn finish_program_progress_bar(pb: &ProgressBar) {
+    pb.set_style(ProgressStyle::with_template(DATA_FINISHED_TPL)
+        .unwrap()
+        .progress_chars(DATA_PROGRESS_CHARS)
+    );
+    pb.finish();
+}
+
+/// Run `f` with a programming progress bar of `len` bytes, which `f` updates
+/// by calling its argument with the number of bytes programmed so far.
+pub(crate) fn with_progress_bar<F>(len: usize, f: F) -> Result<()>
+    where F: FnOnce(&dyn Fn(usize)) -> Result<()>
+{
+    let pb = program_progress_bar(len, "Programming".to_string());
+    f(&|n| pb.set_position(n as u64))?;
+    finish_program_progress_bar(&pb);
+    Ok(())
+}
+
 fn vrefp_to_float(vrefp: u16) -> f32 {
     ((vrefp >> 4) as f32) * 3.0/4096.0
 }
@@ -450,41 +1149,157 @@ fn vrefn_to_float(vrefn: u16) -> f32 {
 }
 
 /// Configuration status register.
+///
+/// The decoded fields are at the same positions on Virtex-6, 7-series,
+/// UltraScale, and UltraScale+ devices, where `xadc_overtemp` reports the
+/// SYSMON over-temperature. Spartan-6 has a 16-bit register with its own
+/// layout: there `init_complete` reports INIT_B, `release_done` reports DONE,
+/// `mmcm_lock` reports DCM_LOCK, and fields it lacks read as zero.
+///
+/// With the `serde` feature enabled, this serialises to the raw register
+/// word along with the family and each decoded field, and deserialises from
+/// the raw word and family.
 #[derive(Copy, Clone)]
-pub struct Status(u32);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "StatusFields", from = "StatusFields"))]
+pub struct Status {
+    family: Family,
+    word: u32,
+}
 
 impl Status {
+    /// Status register `word` of a 7-series device.
     pub fn new(word: u32) -> Self {
-        Self(word)
+        Self::for_family(Family::Series7, word)
+    }
+
+    /// Status register `word` of a `family` device.
+    pub fn for_family(family: Family, word: u32) -> Self {
+        Self { family, word }
+    }
+
+    /// Raw status register word.
+    pub fn word(&self) -> u32 { self.word }
+
+    pub fn family(&self) -> Family { self.family }
+
+    pub fn startup_state(&self) -> u8       { self.field(18, None) }
+    pub fn xadc_overtemp(&self) -> bool     { self.bit(17, None) }
+    pub fn dec_error(&self) -> bool         { self.bit(16, Some(6)) }
+    pub fn id_error(&self) -> bool          { self.bit(15, Some(1)) }
+    pub fn done(&self) -> bool              { self.bit(14, Some(13)) }
+    pub fn release_done(&self) -> bool      { self.bit(13, Some(13)) }
+    pub fn init_b(&self) -> bool            { self.bit(12, Some(12)) }
+    pub fn init_complete(&self) -> bool     { self.bit(11, Some(12)) }
+    pub fn mode(&self) -> u8                { self.field(8, Some(9)) }
+    pub fn ghigh_b(&self) -> bool           { self.bit(7, Some(5)) }
+    pub fn gwe(&self) -> bool               { self.bit(6, Some(4)) }
+    pub fn gts_cfg_b(&self) -> bool         { self.bit(5, Some(3)) }
+    pub fn eos(&self) -> bool               { self.bit(4, None) }
+    pub fn dci_match(&self) -> bool         { self.bit(3, None) }
+    pub fn mmcm_lock(&self) -> bool         { self.bit(2, Some(2)) }
+    pub fn part_secured(&self) -> bool      { self.bit(1, Some(7)) }
+    pub fn crc_error(&self) -> bool         { self.bit(0, Some(0)) }
+
+    /// Offset of a field, which is `spartan6` on Spartan-6 devices.
+    fn offset(&self, offset: usize, spartan6: Option<usize>) -> Option<usize> {
+        match self.family {
+            Family::Spartan6 => spartan6,
+            _ => Some(offset),
+        }
+    }
+
+    fn bit(&self, offset: usize, spartan6: Option<usize>) -> bool {
+        self.offset(offset, spartan6).is_some_and(|offset| (self.word >> offset) & 1 == 1)
+    }
+
+    /// Three-bit field.
+    fn field(&self, offset: usize, spartan6: Option<usize>) -> u8 {
+        self.offset(offset, spartan6).map_or(0, |offset| ((self.word >> offset) & 0b111) as u8)
     }
+}
+
+/// Serialisation representation of `Status`.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StatusFields {
+    word: u32,
+    #[serde(default)]
+    family: Family,
+    #[serde(skip_deserializing)]
+    startup_state: u8,
+    #[serde(skip_deserializing)]
+    xadc_overtemp: bool,
+    #[serde(skip_deserializing)]
+    dec_error: bool,
+    #[serde(skip_deserializing)]
+    id_error: bool,
+    #[serde(skip_deserializing)]
+    done: bool,
+    #[serde(skip_deserializing)]
+    release_done: bool,
+    #[serde(skip_deserializing)]
+    init_b: bool,
+    #[serde(skip_deserializing)]
+    init_complete: bool,
+    #[serde(skip_deserializing)]
+    mode: u8,
+    #[serde(skip_deserializing)]
+    ghigh_b: bool,
+    #[serde(skip_deserializing)]
+    gwe: bool,
+    #[serde(skip_deserializing)]
+    gts_cfg_b: bool,
+    #[serde(skip_deserializing)]
+    eos: bool,
+    #[serde(skip_deserializing)]
+    dci_match: bool,
+    #[serde(skip_deserializing)]
+    mmcm_lock: bool,
+    #[serde(skip_deserializing)]
+    part_secured: bool,
+    #[serde(skip_deserializing)]
+    crc_error: bool,
+}
 
-    pub fn startup_state(&self) -> u8       { ((self.0 >> 18) & 0b111) as u8 }
-    pub fn xadc_overtemp(&self) -> bool     { self.bit(17) }
-    pub fn dec_error(&self) -> bool         { self.bit(16) }
-    pub fn id_error(&self) -> bool          { self.bit(15) }
-    pub fn done(&self) -> bool              { self.bit(14) }
-    pub fn release_done(&self) -> bool      { self.bit(13) }
-    pub fn init_b(&self) -> bool            { self.bit(12) }
-    pub fn init_complete(&self) -> bool     { self.bit(11) }
-    pub fn mode(&self) -> u8                { ((self.0 >> 8) & 0b111) as u8 }
-    pub fn ghigh_b(&self) -> bool           { self.bit(7) }
-    pub fn gwe(&self) -> bool               { self.bit(6) }
-    pub fn gts_cfg_b(&self) -> bool         { self.bit(5) }
-    pub fn eos(&self) -> bool               { self.bit(4) }
-    pub fn dci_match(&self) -> bool         { self.bit(3) }
-    pub fn mmcm_lock(&self) -> bool         { self.bit(2) }
-    pub fn part_secured(&self) -> bool      { self.bit(1) }
-    pub fn crc_error(&self) -> bool         { self.bit(0) }
+#[cfg(feature = "serde")]
+impl From<Status> for StatusFields {
+    fn from(status: Status) -> Self {
+        StatusFields {
+            word: status.word(),
+            family: status.family(),
+            startup_state: status.startup_state(),
+            xadc_overtemp: status.xadc_overtemp(),
+            dec_error: status.dec_error(),
+            id_error: status.id_error(),
+            done: status.done(),
+            release_done: status.release_done(),
+            init_b: status.init_b(),
+            init_complete: status.init_complete(),
+            mode: status.mode(),
+            ghigh_b: status.ghigh_b(),
+            gwe: status.gwe(),
+            gts_cfg_b: status.gts_cfg_b(),
+            eos: status.eos(),
+            dci_match: status.dci_match(),
+            mmcm_lock: status.mmcm_lock(),
+            part_secured: status.part_secured(),
+            crc_error: status.crc_error(),
+        }
+    }
+}
 
-    fn bit(&self, offset: usize) -> bool {
-        (self.0 >> offset) & 1 == 1
+#[cfg(feature = "serde")]
+impl From<StatusFields> for Status {
+    fn from(fields: StatusFields) -> Self {
+        Status::for_family(fields.family, fields.word)
     }
 }
 
 impl fmt::Debug for Status {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_fmt(format_args!(
-            "Status: {:08X}
+            "Status: {:0width$X}
   Startup state: 0b{:03b}
   XADC overtemp: {}
   Decrypt error: {}
@@ -502,57 +1317,386 @@ impl fmt::Debug for Status {
   MMCM lock: {}
   Secured: {}
   CRC error: {}",
-            self.0, self.startup_state(), self.xadc_overtemp(), self.dec_error(), self.id_error(),
+            self.word, self.startup_state(), self.xadc_overtemp(), self.dec_error(), self.id_error(),
             self.done(), self.release_done(), self.init_b(), self.init_complete(), self.mode(),
             self.ghigh_b(), self.gwe(), self.gts_cfg_b(), self.eos(), self.dci_match(),
-            self.mmcm_lock(), self.part_secured(), self.crc_error()))
+            self.mmcm_lock(), self.part_secured(), self.crc_error(),
+            width = self.family.word_bits() / 4))
+    }
+}
+
+/// JTAG operations on the device, optionally also recorded as SVF.
+struct Recorder<T: Tap> {
+    tap: T,
+    svf: Option<SvfWriter>,
+    /// Bypass bits of other SLRs between the selected SLR and TDO.
+    dr_prefix: usize,
+    /// Bypass bits of other SLRs between TDI and the selected SLR.
+    dr_suffix: usize,
+}
+
+impl<T: Tap> Recorder<T> {
+    /// Pad `dr` with the bypass bits of other SLRs.
+    fn pad(&self, dr: &[bool]) -> Vec<bool> {
+        let mut bits = vec![false; self.dr_prefix];
+        bits.extend_from_slice(dr);
+        bits.resize(bits.len() + self.dr_suffix, false);
+        bits
+    }
+
+    /// Remove the bypass bits of other SLRs from captured `dr` of length `n`.
+    fn unpad(&self, dr: Vec<bool>, n: usize) -> Vec<bool> {
+        if self.dr_prefix == 0 && self.dr_suffix == 0 {
+            dr
+        } else {
+            dr[self.dr_prefix..self.dr_prefix + n].to_vec()
+        }
+    }
+
+    fn test_logic_reset(&mut self) -> Result<()> {
+        if let Some(svf) = &mut self.svf { svf.test_logic_reset()?; }
+        self.tap.test_logic_reset()
+    }
+
+    fn run_test_idle(&mut self, n: usize) -> Result<()> {
+        if let Some(svf) = &mut self.svf { svf.run_test_idle(n)?; }
+        self.tap.run_test_idle(n)
+    }
+
+    fn sleep(&mut self, duration: Duration) -> Result<()> {
+        if let Some(svf) = &mut self.svf { svf.sleep(duration)?; }
+        self.tap.sleep(duration)
+    }
+
+    fn write_ir(&mut self, ir: &[bool]) -> Result<()> {
+        if let Some(svf) = &mut self.svf { svf.write_ir(ir)?; }
+        self.tap.write_ir(ir)
+    }
+
+    fn write_dr(&mut self, dr: &[bool]) -> Result<()> {
+        let dr = self.pad(dr);
+        if let Some(svf) = &mut self.svf { svf.write_dr(&dr)?; }
+        self.tap.write_dr(&dr)
+    }
+
+    fn write_dr_cb<F: Fn(usize)>(&mut self, dr: &[bool], cb: F) -> Result<()> {
+        let (n, prefix) = (dr.len(), self.dr_prefix);
+        let dr = self.pad(dr);
+        if let Some(svf) = &mut self.svf { svf.write_dr(&dr)?; }
+        self.tap.write_dr_cb(&dr, &|bits| cb(bits.saturating_sub(prefix).min(n)))
+    }
+
+    fn read_dr(&mut self, n: usize) -> Result<Vec<bool>> {
+        let len = self.dr_prefix + n + self.dr_suffix;
+        if let Some(svf) = &mut self.svf { svf.read_dr(len)?; }
+        let dr = self.tap.read_dr(len)?;
+        Ok(self.unpad(dr, n))
+    }
+
+    fn exchange_dr(&mut self, dr: &[bool]) -> Result<Vec<bool>> {
+        let n = dr.len();
+        let dr = self.pad(dr);
+        if let Some(svf) = &mut self.svf { svf.exchange_dr(&dr)?; }
+        let dr = self.tap.exchange_dr(&dr)?;
+        Ok(self.unpad(dr, n))
     }
 }
 
-pub struct X7 {
-    tap: JTAGTAP,
+/// A Spartan-6, Virtex-6, 7-series, UltraScale, or UltraScale+ device,
+/// accessed through a `Tap`.
+///
+/// The family is given by `X7IDCODE::family` for the device's IDCODE.
+/// Commands a device's family does not support, such as `xadc` on Spartan-6,
+/// fail with `Error::UnsupportedCommand`.
+///
+/// On devices with several SLRs, commands other than JPROGRAM, JSTART,
+/// JSHUTDOWN, and HIGHZ_IO are sent to the selected SLR, by default the
+/// master SLR, with the other SLRs in BYPASS.
+pub struct X7<T: Tap = JTAGTAP> {
+    tap: Recorder<T>,
     idcode: X7IDCODE,
+    revision: u8,
+    slr: usize,
+    compatibility: Compatibility,
 }
 
-impl X7 {
-    pub fn new(tap: JTAGTAP, idcode: X7IDCODE) -> Self {
-        X7 { tap, idcode }
+impl X7<SvfWriter> {
+    /// Create an X7 which records all operations to `svf` without using a probe.
+    ///
+    /// All data read from the device is zero, and status checks are skipped.
+    pub fn record_only(svf: SvfWriter, idcode: X7IDCODE) -> Self {
+        X7::new(svf, idcode)
+    }
+}
+
+impl<T: Tap> X7<T> {
+    pub fn new(tap: T, idcode: X7IDCODE) -> Self {
+        let mut x7 = X7 {
+            tap: Recorder { tap, svf: None, dr_prefix: 0, dr_suffix: 0 },
+            idcode,
+            revision: 0,
+            slr: 0,
+            compatibility: Compatibility::strict(),
+        };
+        x7.set_slr(idcode.master_slr());
+        x7
+    }
+
+    /// Record all further operations to `svf` as well as performing them.
+    pub fn record(&mut self, svf: SvfWriter) {
+        self.tap.svf = Some(svf);
+    }
+
+    /// Stop recording operations, flushing the SVF output.
+    pub fn finish_recording(&mut self) -> Result<()> {
+        if let Some(mut svf) = self.tap.svf.take() {
+            svf.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Whether operations are only recorded, so data read from the device is not valid.
+    pub fn is_record_only(&self) -> bool {
+        !self.tap.tap.is_readback_valid()
+    }
+
+    /// The underlying TAP.
+    pub fn tap(&self) -> &T {
+        &self.tap.tap
+    }
+
+    /// The underlying TAP, mutably.
+    pub fn tap_mut(&mut self) -> &mut T {
+        &mut self.tap.tap
+    }
+
+    /// Consume this X7, returning the underlying TAP.
+    pub fn into_tap(self) -> T {
+        self.tap.tap
     }
 
     pub fn idcode(&self) -> X7IDCODE {
         self.idcode
     }
 
-    /// Read full 64-bit device DNA.
+    /// Set the silicon revision of the device, as read from its IDCODE.
+    pub fn with_revision(mut self, revision: u8) -> Self {
+        self.revision = revision;
+        self
+    }
+
+    /// Device and silicon revision.
+    pub fn device_id(&self) -> DeviceId {
+        DeviceId::new(self.idcode, self.revision)
+    }
+
+    /// Set which bitstreams `check_bitstream` accepts for this device.
+    pub fn set_compatibility(&mut self, compatibility: Compatibility) {
+        self.compatibility = compatibility;
+    }
+
+    /// Check `bitstream` is for this device before programming it.
+    ///
+    /// Bitstreams without an IDCODE are always accepted. Bitstreams for a
+    /// different device which is compatible according to `set_compatibility`
+    /// have their IDCODE changed to match this device. See
+    /// `DeviceId::compatible` for `warn_revision`.
+    pub fn check_bitstream(&self, bitstream: &mut Bitstream, warn_revision: bool) -> Result<()> {
+        let Some(idcode) = bitstream.idcode() else {
+            log::debug!("Bitstream does not contain an IDCODE");
+            return Ok(());
+        };
+        let device = self.device_id();
+        let incompatible = Error::IncompatibleIdcode { bitstream: idcode, jtag: device.idcode() };
+        let Some(id) = DeviceId::try_from_u32(idcode) else {
+            return Err(incompatible);
+        };
+        if device.compatible(id, warn_revision) {
+            Ok(())
+        } else if self.compatibility.compatible(device.device, id.device) {
+            log::info!("Loading {} bitstream into compatible device {}",
+                       id.device.name(), device.device.name());
+            bitstream.set_idcode(device.device as u32)
+        } else {
+            Err(incompatible)
+        }
+    }
+
+    /// Number of SLRs in the device.
+    pub fn slr_count(&self) -> usize {
+        self.idcode.slr_count()
+    }
+
+    /// Currently selected SLR.
+    pub fn slr(&self) -> usize {
+        self.slr
+    }
+
+    /// Select the SLR which further commands are sent to.
+    pub fn select_slr(&mut self, slr: usize) -> Result<()> {
+        if slr >= self.slr_count() {
+            return Err(Error::InvalidSlr(slr, self.slr_count()));
+        }
+        self.set_slr(slr);
+        Ok(())
+    }
+
+    fn set_slr(&mut self, slr: usize) {
+        self.slr = slr;
+        self.tap.dr_prefix = slr;
+        self.tap.dr_suffix = self.slr_count() - 1 - slr;
+    }
+
+    /// Read full device DNA, `Family::dna_bits` long.
     pub fn dna(&mut self) -> Result<Vec<u8>> {
-        self.command(Command::FUSE_DNA)?;
-        let data = self.tap.read_dr(64)?;
+        let family = self.idcode.family();
+        let data = match family {
+            Family::Spartan6 | Family::Virtex6 => {
+                // ISC_DNA is only available in ISC mode.
+                self.tap.test_logic_reset()?;
+                self.command(Command::ISC_ENABLE)?;
+                self.tap.run_test_idle(1)?;
+                self.command(Command::XSC_DNA)?;
+                let data = self.tap.read_dr(family.dna_bits())?;
+                self.command(Command::ISC_DISABLE)?;
+                self.tap.run_test_idle(1)?;
+                self.tap.test_logic_reset()?;
+                data
+            },
+            _ => {
+                self.command(Command::FUSE_DNA)?;
+                self.tap.read_dr(family.dna_bits())?
+            },
+        };
         let dna = bits_to_bytes(&data);
         log::info!("Read DNA: {:02X?}", dna);
         Ok(dna)
     }
 
+    /// Capture the boundary register using SAMPLE/PRELOAD.
+    ///
+    /// `preload` is shifted in and loaded into the boundary register update
+    /// latches, without affecting device operation. Returns the captured
+    /// boundary register contents, with cell 0 first.
+    pub fn sample(&mut self, preload: &[bool]) -> Result<Vec<bool>> {
+        self.command(Command::SAMPLE)?;
+        let captured = self.tap.exchange_dr(preload)?;
+        self.tap.run_test_idle(1)?;
+        Ok(captured)
+    }
+
+    /// Load the boundary register update latches using SAMPLE/PRELOAD.
+    pub fn preload(&mut self, preload: &[bool]) -> Result<()> {
+        self.command(Command::SAMPLE)?;
+        self.tap.write_dr(preload)?;
+        self.tap.run_test_idle(1)?;
+        Ok(())
+    }
+
+    /// Drive the boundary register contents onto the device pins using EXTEST.
+    ///
+    /// Returns the boundary register contents captured before `drive` is applied.
+    /// Call `preload` with the same data first to avoid glitches when entering EXTEST.
+    /// Pins remain driven until `test_logic_reset` is called.
+    pub fn extest(&mut self, drive: &[bool]) -> Result<Vec<bool>> {
+        self.command(Command::EXTEST)?;
+        let captured = self.tap.exchange_dr(drive)?;
+        self.tap.run_test_idle(1)?;
+        Ok(captured)
+    }
+
+    /// Shift data through a BSCANE2 user data register using USER1 to USER4.
+    ///
+    /// Selects USER`n`, then shifts `len` bits through the register with `bits_in`
+    /// first, padded with zeros to `len` bits. Returns the `len` bits captured.
+    pub fn user_dr(&mut self, n: u8, bits_in: &[bool], len: usize) -> Result<Vec<bool>> {
+        let command = match n {
+            1 => Command::USER1,
+            2 => Command::USER2,
+            3 => Command::USER3,
+            4 => Command::USER4,
+            n => return Err(Error::InvalidUserRegister(n)),
+        };
+        if bits_in.len() > len {
+            return Err(Error::UserDataTooLong(bits_in.len(), len));
+        }
+        let mut bits = bits_in.to_vec();
+        bits.resize(len, false);
+        self.command(command)?;
+        let captured = self.tap.exchange_dr(&bits)?;
+        self.tap.run_test_idle(1)?;
+        Ok(captured)
+    }
+
+    /// Float all user I/O pins using HIGHZ_IO.
+    ///
+    /// Pins remain high impedance until `test_logic_reset` is called.
+    pub fn highz(&mut self) -> Result<()> {
+        self.command(Command::HIGHZ_IO)?;
+        self.tap.run_test_idle(1)?;
+        Ok(())
+    }
+
+    /// Halt the running design by running the shutdown sequence with JSHUTDOWN.
+    ///
+    /// Outputs are tri-stated and writes to design state are disabled
+    /// until `startup` is called.
+    pub fn shutdown(&mut self) -> Result<()> {
+        self.tap.test_logic_reset()?;
+        self.command(Command::JSHUTDOWN)?;
+        self.tap.run_test_idle(2000)?;
+        self.tap.test_logic_reset()?;
+        Ok(())
+    }
+
+    /// Restart a halted design by running the startup sequence with JSTART.
+    pub fn startup(&mut self) -> Result<()> {
+        self.tap.test_logic_reset()?;
+        self.command(Command::JSTART)?;
+        self.tap.run_test_idle(2000)?;
+        self.tap.test_logic_reset()?;
+        Ok(())
+    }
+
+    /// Reset the TAP, returning the device to normal operation.
+    pub fn test_logic_reset(&mut self) -> Result<()> {
+        self.tap.test_logic_reset()
+    }
+
     /// Read STATUS register content.
     pub fn status(&mut self) -> Result<Status> {
         self.tap.test_logic_reset()?;
         self.tap.run_test_idle(5)?;
         self.command(Command::CFG_IN)?;
-        let mut bits = Vec::new();
-        bitvec::append_u32(&mut bits, 0xaa99_5566u32.reverse_bits());
-        bitvec::append_u32(&mut bits, 0x2000_0000u32.reverse_bits());
-        bitvec::append_u32(&mut bits, 0x2800_e001u32.reverse_bits());
-        bitvec::append_u32(&mut bits, 0x2000_0000u32.reverse_bits());
-        bitvec::append_u32(&mut bits, 0x2000_0000u32.reverse_bits());
+        // Sync, NOOP, read one word from STAT, then two NOOPs to flush it out.
+        let family = self.idcode.family();
+        let words: &[u32] = match family {
+            Family::Spartan6 => &[0xaa99, 0x5566, 0x2000, 0x2901, 0x2000, 0x2000],
+            _ => &[0xaa99_5566, 0x2000_0000, 0x2800_e001, 0x2000_0000, 0x2000_0000],
+        };
+        // Configuration words are shifted MSb first.
+        let n = family.word_bits();
+        let bits: Vec<bool> = words.iter().flat_map(|w| (0..n).rev().map(move |i| (w >> i) & 1 == 1)).collect();
         self.tap.write_dr(&bits)?;
         self.command(Command::CFG_OUT)?;
-        let status = bits_to_bytes(&self.tap.read_dr(32)?);
-        let status = u32::from_le_bytes([status[0], status[1], status[2], status[3]]);
-        let status = Status::new(status.reverse_bits());
+        let word = self.tap.read_dr(n)?.iter().fold(0u32, |w, b| (w << 1) | *b as u32);
+        let status = Status::for_family(family, word);
         log::debug!("{:?}", status);
         self.tap.test_logic_reset()?;
         Ok(status)
     }
 
+    /// Read the STATUS register of every SLR, in order.
+    pub fn status_all(&mut self) -> Result<Vec<Status>> {
+        let selected = self.slr;
+        let statuses = (0..self.slr_count()).map(|slr| {
+            self.set_slr(slr);
+            self.status()
+        }).collect();
+        self.set_slr(selected);
+        statuses
+    }
+
     /// Read XADC registers
     pub fn xadc(&mut self) -> Result<XadcReading> {
         // Select XADC mode
@@ -561,7 +1705,8 @@ impl X7 {
         self.command(Command::XADC_DRP)?;
 
         let reading = XadcReading {
-            temperature: MinMaxNow::from_temperature(
+            temperature: MinMaxNow::from_family_temperature(
+                self.idcode.family(),
                 self.read_xadc_reg(XadcReg::MinTemp)?,
                 self.read_xadc_reg(XadcReg::MaxTemp)?,
                 self.read_xadc_reg(XadcReg::Temperature)?,
@@ -630,27 +1775,7 @@ impl X7 {
     ///
     /// The FPGA is reset and begins running the new bitstream after programming.
     pub fn program_progress(&mut self, data: &[u8]) -> Result<()> {
-        const DATA_PROGRESS_TPL: &str =
-            " {msg} [{bar:40.cyan/black}] {bytes}/{total_bytes} ({bytes_per_sec}; {eta_precise})";
-        const DATA_FINISHED_TPL: &str =
-            " {msg} [{bar:40.green/black}] {bytes}/{total_bytes} ({bytes_per_sec}; {eta_precise})";
-        const DATA_PROGRESS_CHARS: &str = "━╸━";
-        let pb = ProgressBar::new(data.len() as u64).with_style(
-            ProgressStyle::with_template(DATA_PROGRESS_TPL)
-                .unwrap()
-                .progress_chars(DATA_PROGRESS_CHARS));
-        pb.set_message("Programming");
-        pb.set_position(0);
-
-        self.program_cb(data, |n| pb.set_position(n as u64))?;
-
-        pb.set_style(ProgressStyle::with_template(DATA_FINISHED_TPL)
-            .unwrap()
-            .progress_chars(DATA_PROGRESS_CHARS)
-        );
-
-        pb.finish();
-        Ok(())
+        with_progress_bar(data.len(), |cb| self.program_cb(data, cb))
     }
 
     /// Program a bitstream to SRAM, calling `cb` with the number of bytes programmed so far.
@@ -662,9 +1787,11 @@ impl X7 {
         self.tap.test_logic_reset()?;
         self.command(Command::JPROGRAM)?;
         self.tap.run_test_idle(1)?;
-        std::thread::sleep(Duration::from_millis(20));
+        self.tap.sleep(Duration::from_millis(20))?;
 
-        // Enter configuration mode.
+        // Enter configuration mode on the master SLR, which configures any others.
+        let selected = self.slr;
+        self.set_slr(self.idcode.master_slr());
         self.tap.test_logic_reset()?;
         self.command(Command::CFG_IN)?;
 
@@ -675,7 +1802,9 @@ impl X7 {
         let bits = bytes_to_bits(&data, data.len() * 8)?;
 
         // Write bitstream, passing the callback through.
-        self.tap.write_dr_cb(&bits, |n| cb(n / 8))?;
+        let result = self.tap.write_dr_cb(&bits, |n| cb(n / 8));
+        self.set_slr(selected);
+        result?;
 
         // Return to Run-Test/Idle to complete programming.
         self.tap.run_test_idle(1)?;
@@ -700,57 +1829,92 @@ impl X7 {
     }
 
     fn check_ready_to_program(&mut self) -> Result<()> {
-        log::debug!("Checking status before programming...");
-        let status = self.status()?;
-        if !status.init_complete() {
-            log::error!("FPGA init not complete");
-            return Err(Error::BadStatus);
+        if self.is_record_only() {
+            return Ok(());
         }
-        if !status.init_b() {
-            log::error!("FPGA INIT_B still low");
-            return Err(Error::BadStatus);
+        log::debug!("Checking status before programming...");
+        for (slr, status) in self.status_all()?.iter().enumerate() {
+            log::debug!("SLR {slr}: {status:?}");
+            if !status.init_complete() {
+                log::error!("FPGA init not complete");
+                return Err(Error::BadStatus);
+            }
+            if !status.init_b() {
+                log::error!("FPGA INIT_B still low");
+                return Err(Error::BadStatus);
+            }
         }
         Ok(())
     }
 
     fn check_programmed_ok(&mut self) -> Result<()> {
-        log::debug!("Checking status after programming...");
-        let status = self.status()?;
-        if !status.init_complete() {
-            log::error!("Init not complete");
-            return Err(Error::BadStatus);
-        }
-        if !status.init_b() {
-            log::error!("INIT_B still low");
-            return Err(Error::BadStatus);
-        }
-        if !status.done() {
-            log::error!("DONE still low");
-            return Err(Error::BadStatus);
-        }
-        if !status.release_done() {
-            log::error!("DONE not released");
-            return Err(Error::BadStatus);
+        if self.is_record_only() {
+            return Ok(());
         }
-        if status.dec_error() {
-            log::error!("Decrypt error");
-            return Err(Error::BadStatus);
-        }
-        if status.id_error() {
-            log::error!("ID error");
-            return Err(Error::BadStatus);
-        }
-        if status.crc_error() {
-            log::error!("CRC error");
-            return Err(Error::BadStatus);
+        log::debug!("Checking status after programming...");
+        for (slr, status) in self.status_all()?.iter().enumerate() {
+            log::debug!("SLR {slr}: {status:?}");
+            if !status.init_complete() {
+                log::error!("Init not complete");
+                return Err(Error::BadStatus);
+            }
+            if !status.init_b() {
+                log::error!("INIT_B still low");
+                return Err(Error::BadStatus);
+            }
+            if !status.done() {
+                log::error!("DONE still low");
+                return Err(Error::BadStatus);
+            }
+            if !status.release_done() {
+                log::error!("DONE not released");
+                return Err(Error::BadStatus);
+            }
+            if status.dec_error() {
+                log::error!("Decrypt error");
+                return Err(Error::BadStatus);
+            }
+            if status.id_error() {
+                log::error!("ID error");
+                return Err(Error::BadStatus);
+            }
+            if status.crc_error() {
+                log::error!("CRC error");
+                return Err(Error::BadStatus);
+            }
         }
         Ok(())
     }
 
     /// Load a command into the IR.
+    ///
+    /// On devices with several SLRs, JPROGRAM, JSTART, JSHUTDOWN, and HIGHZ_IO
+    /// are loaded into every SLR, and other commands only into the selected SLR.
     fn command(&mut self, command: Command) -> Result<()> {
         log::trace!("Loading command {:?}", command);
-        Ok(self.tap.write_ir(&command.bits())?)
+        if self.idcode.has_gtz() {
+            return Err(Error::GtzUnsupported(self.idcode.name()));
+        }
+        let family = self.idcode.family();
+        let unsupported = |command: Command| Error::UnsupportedCommand(format!("{command:?}"), family.name());
+        let bits = command.bits(family).ok_or_else(|| unsupported(command))?;
+        if self.slr_count() == 1 {
+            return self.tap.write_ir(&bits);
+        }
+        let broadcast = matches!(
+            command,
+            Command::JPROGRAM | Command::JSTART | Command::JSHUTDOWN | Command::HIGHZ_IO
+        );
+        let mut ir = Vec::with_capacity(self.idcode.ir_length());
+        for slr in 0..self.slr_count() {
+            if broadcast || slr == self.slr {
+                ir.extend(&bits);
+            } else {
+                ir.extend(Command::BYPASS.bits(family).ok_or_else(|| unsupported(Command::BYPASS))?);
+            }
+        }
+        ir.resize(self.idcode.ir_length(), true);
+        self.tap.write_ir(&ir)
     }
 }
 
@@ -790,4 +1954,544 @@ impl Bitstream {
     pub fn data(&self) -> &[u8] {
         &self.data[..]
     }
+
+    /// Find the IDCODE the bitstream is checked against, if any.
+    ///
+    /// Searches for a write to the IDCODE register within the first packets
+    /// following the sync word, in either 32-bit or Spartan-6 16-bit words.
+    pub fn idcode(&self) -> Option<u32> {
+        self.idcode32().or_else(|| self.spartan6_idcode())
+    }
+
+    /// Offset of the first byte after the sync word.
+    fn sync_end(&self) -> Option<usize> {
+        const SYNC: [u8; 4] = [0xAA, 0x99, 0x55, 0x66];
+        Some(self.data.windows(4).position(|w| w == SYNC)? + 4)
+    }
+
+    fn idcode32(&self) -> Option<u32> {
+        const WRITE_IDCODE: u32 = 0x3001_8001;
+        const MAX_WORDS: usize = 256;
+        let words: Vec<u32> = self.data[self.sync_end()?..].chunks_exact(4).take(MAX_WORDS)
+            .map(|w| u32::from_be_bytes([w[0], w[1], w[2], w[3]]))
+            .collect();
+        words.windows(2).find(|w| w[0] == WRITE_IDCODE).map(|w| w[1])
+    }
+
+    /// IDCODE of a Spartan-6 bitstream, written as two 16-bit words.
+    fn spartan6_idcode(&self) -> Option<u32> {
+        const WRITE_IDCODE: u16 = 0x31C2;
+        const MAX_WORDS: usize = 256;
+        let words: Vec<u16> = self.data[self.sync_end()?..].chunks_exact(2).take(MAX_WORDS)
+            .map(|w| u16::from_be_bytes([w[0], w[1]]))
+            .collect();
+        words.windows(3).find(|w| w[0] == WRITE_IDCODE).map(|w| (w[1] as u32) << 16 | w[2] as u32)
+    }
+
+    /// Change the IDCODE the bitstream is checked against to `idcode`.
+    ///
+    /// All configuration packets are parsed to update any CRC checks, which
+    /// cover the IDCODE. Spartan-6 bitstreams are not supported.
+    pub fn set_idcode(&mut self, idcode: u32) -> Result<()> {
+        const SYNC: [u8; 4] = [0xAA, 0x99, 0x55, 0x66];
+        const REG_CRC: u32 = 0x00;
+        const REG_CMD: u32 = 0x04;
+        const REG_IDCODE: u32 = 0x0C;
+        const CMD_RCRC: u32 = 0x07;
+        let err = |msg: &str| Error::BitstreamParse(msg.to_string());
+        if self.idcode32().is_none() && self.spartan6_idcode().is_some() {
+            return Err(err("changing the IDCODE of Spartan-6 bitstreams is not supported"));
+        }
+
+        let data = &mut self.data;
+        let mut pos = data.windows(4).position(|w| w == SYNC).ok_or_else(|| err("no sync word"))? + 4;
+        let word = |data: &[u8], pos: usize| u32::from_be_bytes([data[pos], data[pos+1], data[pos+2], data[pos+3]]);
+        let mut crc = 0;
+        let mut reg = 0;
+        let mut found = false;
+        while pos + 4 <= data.len() {
+            let header = word(data, pos);
+            pos += 4;
+            let count = match header >> 29 {
+                0b001 => {
+                    reg = (header >> 13) & 0x1F;
+                    header & 0x7FF
+                },
+                0b010 => header & 0x07FF_FFFF,
+                _ => continue,
+            };
+            // Only writes are followed by data.
+            if (header >> 27) & 0b11 != 0b10 {
+                continue;
+            }
+            if pos + 4 * count as usize > data.len() {
+                return Err(err("packet extends beyond end of bitstream"));
+            }
+            for _ in 0..count {
+                let mut value = word(data, pos);
+                match reg {
+                    REG_CRC => {
+                        value = crc;
+                        crc = 0;
+                    },
+                    REG_IDCODE => {
+                        value = idcode;
+                        found = true;
+                    },
+                    _ => (),
+                }
+                if reg != REG_CRC {
+                    crc = sim::crc_update(crc, reg as u8, value);
+                }
+                if reg == REG_CMD && value & 0x1F == CMD_RCRC {
+                    crc = 0;
+                }
+                data[pos..pos+4].copy_from_slice(&value.to_be_bytes());
+                pos += 4;
+            }
+        }
+        if found { Ok(()) } else { Err(err("no IDCODE write found")) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mock::{MockTap, Op};
+
+    /// Bits of `word`, least significant bit first.
+    fn bits32(word: u32) -> Vec<bool> {
+        let mut bits = Vec::new();
+        bitvec::append_u32(&mut bits, word);
+        bits
+    }
+
+    /// IR bits of a 7-series `command`.
+    fn ir(command: Command) -> Vec<bool> {
+        command.bits(Family::Series7).unwrap()
+    }
+
+    /// The low `len` bits of `opcode`, least significant bit first.
+    fn opcode_bits(opcode: u16, len: usize) -> Vec<bool> {
+        (0..len).map(|i| (opcode >> i) & 1 == 1).collect()
+    }
+
+    /// Configuration words as shifted into CFG_IN, most significant bit of each word first.
+    fn cfg_words(words: &[u32]) -> Vec<bool> {
+        words.iter().flat_map(|w| bits32(w.reverse_bits())).collect()
+    }
+
+    /// Operations performed by `X7::status` on a single-SLR device.
+    fn status_ops() -> Vec<Op> {
+        vec![
+            Op::TestLogicReset,
+            Op::RunTestIdle(5),
+            Op::WriteIr(ir(Command::CFG_IN)),
+            Op::WriteDr(cfg_words(&[0xAA99_5566, 0x2000_0000, 0x2800_E001, 0x2000_0000, 0x2000_0000])),
+            Op::WriteIr(ir(Command::CFG_OUT)),
+            Op::ReadDr(32),
+            Op::TestLogicReset,
+        ]
+    }
+
+    /// Queue `status` to be read back through CFG_OUT.
+    fn push_status(tap: &mut MockTap, status: u32) {
+        tap.push_dr_u32(status.reverse_bits());
+    }
+
+    #[test]
+    fn family() {
+        assert_eq!(X7IDCODE::X7S6.family(), Family::Series7);
+        assert_eq!(X7IDCODE::X7A35T.family(), Family::Series7);
+        assert_eq!(X7IDCODE::X7VX1140T.family(), Family::Series7);
+        assert_eq!(X7IDCODE::X7Z100.family(), Family::Series7);
+        assert_eq!(X7IDCODE::XKU035.family(), Family::UltraScale);
+        assert_eq!(X7IDCODE::XKU060.family(), Family::UltraScale);
+        assert_eq!(X7IDCODE::XVU095.family(), Family::UltraScale);
+        assert_eq!(X7IDCODE::XKU3P.family(), Family::UltraScalePlus);
+        assert_eq!(X7IDCODE::XKU9P.family(), Family::UltraScalePlus);
+        assert_eq!(X7IDCODE::XAU10P.family(), Family::UltraScalePlus);
+        assert_eq!(X7IDCODE::XVU3P.family(), Family::UltraScalePlus);
+        assert_eq!(X7IDCODE::X6SLX9.family(), Family::Spartan6);
+        assert_eq!(X7IDCODE::X6SLX150T.family(), Family::Spartan6);
+        assert_eq!(X7IDCODE::X6VLX240T.family(), Family::Virtex6);
+        assert_eq!(X7IDCODE::X6VCX75T.family(), Family::Virtex6);
+        assert_eq!(X7IDCODE::X6SLX9.ir_length(), 6);
+        assert_eq!(X7IDCODE::X6VLX240T.ir_length(), 10);
+    }
+
+    #[test]
+    fn family_opcodes() {
+        assert_eq!(Command::CFG_IN.opcode(Family::UltraScale), Some(0x05));
+        assert_eq!(Command::EXTEST.opcode(Family::Spartan6), Some(0x0F));
+        assert_eq!(Command::EXTEST.opcode(Family::Virtex6), Some(0x3C0));
+        assert_eq!(Command::USER3.opcode(Family::Spartan6), Some(0x1A));
+        assert_eq!(Command::USER3.opcode(Family::Virtex6), Some(0x3E2));
+        assert_eq!(Command::XSC_DNA.opcode(Family::Spartan6), Some(0x30));
+        assert_eq!(Command::XSC_DNA.opcode(Family::Virtex6), Some(0x3D7));
+        assert_eq!(Command::XADC_DRP.opcode(Family::Spartan6), None);
+        assert_eq!(Command::XADC_DRP.opcode(Family::Virtex6), Some(0x3F7));
+        assert_eq!(Command::FUSE_DNA.opcode(Family::Virtex6), None);
+        assert_eq!(Command::BYPASS.bits(Family::Virtex6), Some(vec![true; 10]));
+    }
+
+    #[test]
+    fn family_temperature() {
+        let close = |a: f32, b: f32| (a - b).abs() < 0.01;
+        // 7-series XADC codes are 12 bits, left aligned.
+        assert!(close(Family::Series7.temperature(0x9B40), 32.483));
+        assert!(close(Family::Series7.temperature(0x9B4F), 32.483));
+        assert!(close(Family::Series7.temperature(0x0000), -273.15));
+        // UltraScale and UltraScale+ SYSMON codes use all 16 bits.
+        assert!(close(Family::UltraScale.temperature(0x9B40), 31.1678));
+        assert!(close(Family::UltraScale.temperature(0x0000), -273.8195));
+        assert!(close(Family::UltraScalePlus.temperature(0x9B40), 28.6402));
+        assert!(close(Family::UltraScalePlus.temperature(0xFFFF), 229.0753));
+    }
+
+    #[test]
+    fn try_from_name() {
+        assert_eq!(X7IDCODE::try_from_name("X7A35T"), Some(X7IDCODE::X7A35T));
+        assert_eq!(X7IDCODE::try_from_name("XC7A35T"), Some(X7IDCODE::X7A35T));
+        assert_eq!(X7IDCODE::try_from_name("xa7z020"), Some(X7IDCODE::X7Z020));
+        assert_eq!(X7IDCODE::try_from_name("XQ7K325T-2RF900I"), Some(X7IDCODE::X7K325T));
+        assert_eq!(X7IDCODE::try_from_name("XCKU5P-FFVB676"), Some(X7IDCODE::XKU5P));
+        assert_eq!(X7IDCODE::try_from_name("XC7V575T"), Some(X7IDCODE::X7V585T));
+        assert_eq!(X7IDCODE::try_from_name("XC6SLX9-2TQG144C"), Some(X7IDCODE::X6SLX9));
+        assert_eq!(X7IDCODE::try_from_name("xc6vlx240t"), Some(X7IDCODE::X6VLX240T));
+        assert_eq!(X7IDCODE::try_from_name("XC7A36T"), None);
+        assert_eq!(X7IDCODE::try_from_name("XZ7A35T"), None);
+        assert_eq!(X7IDCODE::try_from_name(""), None);
+    }
+
+    #[test]
+    fn revision_mismatch() {
+        assert_eq!(DeviceId::try_from_u32(0x3372_7093), Some(DeviceId::new(X7IDCODE::X7Z020, 3)));
+        assert_eq!(DeviceId::new(X7IDCODE::X7Z020, 3).idcode(), 0x3372_7093);
+        // Revision differences never affect compatibility.
+        let device = DeviceId::new(X7IDCODE::X7Z020, 0);
+        assert!(device.compatible(DeviceId::new(X7IDCODE::X7Z020, 3), true));
+        assert!(!device.compatible(DeviceId::new(X7IDCODE::X7Z010, 0), true));
+    }
+
+    /// Reference configuration CRC, computed MSB-first with the unreflected
+    /// CRC-32C polynomial, independently of `sim::crc_update`.
+    fn reference_crc(writes: &[(u32, u32)]) -> u32 {
+        let mut crc = 0u32;
+        for (reg, value) in writes {
+            let data = (*reg as u64) << 32 | *value as u64;
+            for i in 0..37 {
+                let bit = (data >> i) as u32 & 1;
+                crc = (crc << 1) ^ if (crc >> 31) ^ bit == 1 { 0x1EDC_6F41 } else { 0 };
+            }
+        }
+        crc.reverse_bits()
+    }
+
+    #[test]
+    fn reference_crc_is_crc32c() {
+        // The standard CRC-32C check value, using the same bit ordering.
+        let mut crc = !0u32;
+        for byte in b"123456789" {
+            for i in 0..8 {
+                let bit = (*byte as u32 >> i) & 1;
+                crc = (crc << 1) ^ if (crc >> 31) ^ bit == 1 { 0x1EDC_6F41 } else { 0 };
+            }
+        }
+        assert_eq!(!crc.reverse_bits(), 0xE306_9283);
+    }
+
+    /// Synthetic bitstream writing `idcode` and some frame data, with two CRC checks.
+    ///
+    /// No vendor bitstream is available to test against, so the CRCs are
+    /// computed by `reference_crc` and the result checked by `SimX7`.
+    fn crc_bitstream(idcode: u32) -> Vec<u8> {
+        let fdri: Vec<u32> = (0..Family::Series7.frame_words() as u32).map(|i| i * 0x0101_0101).collect();
+        let mut first = vec![(0x0C, idcode), (0x01, 0)];
+        first.extend(fdri.iter().map(|w| (0x02, *w)));
+        let second = [(0x04, 0x05)];
+        let crcs = [reference_crc(&first), reference_crc(&second)];
+
+        let mut words = vec![0xFFFF_FFFF, 0xAA99_5566, 0x2000_0000, 0x3000_8001, 0x0000_0007];
+        words.extend([0x3001_8001, idcode, 0x3000_2001, 0]);
+        words.push(0x3000_4000 | fdri.len() as u32);
+        words.extend(&fdri);
+        words.extend([0x3000_0001, crcs[0]]);
+        words.extend([0x3000_8001, 0x0000_0005, 0x3000_0001, crcs[1]]);
+        words.extend([0x3000_8001, 0x0000_000D, 0x2000_0000, 0x2000_0000]);
+        words.iter().flat_map(|w| w.to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn set_idcode_updates_crc() {
+        let data = crc_bitstream(X7IDCODE::X7A35T as u32);
+        let expected = crc_bitstream(X7IDCODE::X7A50T as u32);
+        let mut bitstream = Bitstream::new(data.clone());
+        assert_eq!(bitstream.idcode(), Some(X7IDCODE::X7A35T as u32));
+        bitstream.set_idcode(X7IDCODE::X7A50T as u32).unwrap();
+        assert_eq!(bitstream.idcode(), Some(X7IDCODE::X7A50T as u32));
+        assert_eq!(bitstream.data(), expected);
+        // Only the IDCODE and the CRC covering it change.
+        let changed: Vec<usize> = data.chunks(4).zip(bitstream.data().chunks(4))
+            .enumerate().filter(|(_, (a, b))| a != b).map(|(i, _)| i).collect();
+        assert_eq!(changed, [6, 11 + Family::Series7.frame_words()]);
+
+        // The device accepts the updated bitstream.
+        let mut x7 = X7::new(sim::SimX7::new(X7IDCODE::X7A50T), X7IDCODE::X7A50T);
+        x7.program(bitstream.data()).unwrap();
+        assert!(x7.tap().is_configured());
+    }
+
+    #[test]
+    fn set_idcode_errors() {
+        let mut bitstream = Bitstream::new(vec![0; 16]);
+        assert!(matches!(bitstream.set_idcode(0), Err(Error::BitstreamParse(_))));
+        let mut bitstream = Bitstream::new(vec![0xAA, 0x99, 0x55, 0x66, 0x20, 0, 0, 0]);
+        assert!(matches!(bitstream.set_idcode(0), Err(Error::BitstreamParse(_))));
+    }
+
+    #[test]
+    fn status() {
+        let mut tap = MockTap::new();
+        // DONE, RELEASE_DONE, INIT_B, INIT_COMPLETE, JTAG mode, startup state 4, CRC error.
+        push_status(&mut tap, 0x0010_7D01);
+        let mut x7 = X7::new(tap, X7IDCODE::X7A35T);
+        let status = x7.status().unwrap();
+        assert_eq!(x7.tap().ops(), status_ops());
+        assert_eq!(x7.tap().pending_responses(), 0);
+        assert_eq!(status.word(), 0x0010_7D01);
+        assert!(status.done() && status.release_done() && status.init_b() && status.init_complete());
+        assert!(status.crc_error());
+        assert!(!status.id_error() && !status.dec_error());
+        assert_eq!(status.mode(), 0b101);
+        assert_eq!(status.startup_state(), 4);
+    }
+
+    #[test]
+    fn spartan6_status() {
+        let mut tap = MockTap::new();
+        // DONE, INIT_B, JTAG mode, GWE, GTS_CFG_B, DCM_LOCK, CRC error.
+        tap.push_dr(opcode_bits(0x3A1D_u16.reverse_bits(), 16));
+        let mut x7 = X7::new(tap, X7IDCODE::X6SLX9);
+        let status = x7.status().unwrap();
+        let words = [0xAA99, 0x5566, 0x2000, 0x2901, 0x2000, 0x2000];
+        assert_eq!(x7.tap().ops(), [
+            Op::TestLogicReset,
+            Op::RunTestIdle(5),
+            Op::WriteIr(opcode_bits(0x05, 6)),
+            Op::WriteDr(words.iter().flat_map(|w: &u16| opcode_bits(w.reverse_bits(), 16)).collect()),
+            Op::WriteIr(opcode_bits(0x04, 6)),
+            Op::ReadDr(16),
+            Op::TestLogicReset,
+        ]);
+        assert_eq!(status.word(), 0x3A1D);
+        assert_eq!(status.family(), Family::Spartan6);
+        assert!(status.done() && status.release_done() && status.init_b() && status.init_complete());
+        assert!(status.gwe() && status.gts_cfg_b() && status.mmcm_lock() && status.crc_error());
+        assert!(!status.ghigh_b() && !status.part_secured() && !status.id_error() && !status.eos());
+        assert_eq!(status.mode(), 0b101);
+        assert_eq!(status.startup_state(), 0);
+    }
+
+    #[test]
+    fn xadc_unsupported() {
+        // Spartan-6 has no system monitor, so no IR or DR is shifted.
+        let mut x7 = X7::new(MockTap::new(), X7IDCODE::X6SLX9);
+        let err = x7.xadc().unwrap_err();
+        assert!(matches!(&err, Error::UnsupportedCommand(c, "Spartan-6") if c == "XADC_DRP"));
+        assert_eq!(err.to_string(), "XADC_DRP is not supported on Spartan-6 devices.");
+        assert_eq!(x7.tap().ops(), [Op::TestLogicReset, Op::RunTestIdle(5)]);
+
+        // Virtex-6 reads its system monitor through SYSMON_DRP.
+        let mut x7 = X7::new(MockTap::new(), X7IDCODE::X6VLX240T);
+        x7.xadc().unwrap();
+        assert_eq!(x7.tap().ir_writes().next(), Some(&opcode_bits(0x3F7, 10)[..]));
+    }
+
+    #[test]
+    fn xadc() {
+        let mut tap = MockTap::new();
+        // Readings in the order they are read: min, max, then current value.
+        let regs = [
+            (XadcReg::MinTemp, 0x9000), (XadcReg::MaxTemp, 0xA000), (XadcReg::Temperature, 0x9B40),
+            (XadcReg::MinVccint, 0x5550), (XadcReg::MaxVccint, 0x5560), (XadcReg::Vccint, 0x5555),
+            (XadcReg::MinVccaux, 0x9990), (XadcReg::MaxVccaux, 0x99A0), (XadcReg::Vccaux, 0x9999),
+            (XadcReg::MinVccbram, 0x5550), (XadcReg::MaxVccbram, 0x5560), (XadcReg::Vccbram, 0x5555),
+            (XadcReg::MinVccpint, 0), (XadcReg::MaxVccpint, 0), (XadcReg::Vccpint, 0),
+            (XadcReg::MinVccpaux, 0), (XadcReg::MaxVccpaux, 0), (XadcReg::Vccpaux, 0),
+            (XadcReg::MinVccoddr, 0), (XadcReg::MaxVccoddr, 0), (XadcReg::Vccoddr, 0),
+            (XadcReg::Vrefp, 0x6660), (XadcReg::Vrefn, 0x0000), (XadcReg::Flag, 0x0008),
+        ];
+        for (reg, value) in regs {
+            tap.push_dr_u32(((reg as u32) << 16) | value as u32);
+        }
+        let mut x7 = X7::new(tap, X7IDCODE::X7A35T);
+        let reading = x7.xadc().unwrap();
+
+        let mut ops = vec![Op::TestLogicReset, Op::RunTestIdle(5), Op::WriteIr(ir(Command::XADC_DRP))];
+        for (reg, _) in regs {
+            ops.push(Op::WriteDr(bits32(0x0400_0000 | (reg as u32) << 16)));
+            ops.push(Op::RunTestIdle(15));
+            ops.push(Op::ReadDr(32));
+        }
+        ops.push(Op::TestLogicReset);
+        assert_eq!(x7.tap().ops(), ops);
+        assert_eq!(x7.tap().pending_responses(), 0);
+
+        let close = |a: f32, b: f32| (a - b).abs() < 0.01;
+        let temp = reading.temperature();
+        assert!(close(temp.current(), 0x9B4 as f32 * 503.975 / 4096.0 - 273.15), "{temp:?}");
+        assert!(close(temp.min(), 0x900 as f32 * 503.975 / 4096.0 - 273.15));
+        assert!(close(temp.max(), 0xA00 as f32 * 503.975 / 4096.0 - 273.15));
+        assert!(close(reading.vccint().current(), 1.0));
+        assert!(close(reading.vccaux().current(), 1.8));
+        assert!(close(reading.vccbram().max(), 1.0));
+        assert!(close(reading.vrefp(), 1.2));
+        assert_eq!(reading.flag(), 0x0008);
+    }
+
+    #[test]
+    fn dna() {
+        let mut tap = MockTap::new();
+        let dna = 0x0123_4567_89AB_CDEFu64;
+        tap.push_dr((0..64).map(|i| (dna >> i) & 1 == 1).collect());
+        let mut x7 = X7::new(tap, X7IDCODE::X7A35T);
+        assert_eq!(x7.dna().unwrap(), dna.to_le_bytes());
+        assert_eq!(x7.tap().ops(), [Op::WriteIr(ir(Command::FUSE_DNA)), Op::ReadDr(64)]);
+
+        for (idcode, [enable, isc_dna, disable], len) in [
+            (X7IDCODE::X6SLX9, [0x10, 0x30, 0x16], 6),
+            (X7IDCODE::X6VLX240T, [0x3D0, 0x3D7, 0x3D6], 10),
+        ] {
+            let mut tap = MockTap::new();
+            tap.push_dr((0..57).map(|i| (dna >> i) & 1 == 1).collect());
+            let mut x7 = X7::new(tap, idcode);
+            assert_eq!(x7.dna().unwrap(), (dna & ((1 << 57) - 1)).to_le_bytes());
+            assert_eq!(x7.tap().ops(), [
+                Op::TestLogicReset,
+                Op::WriteIr(opcode_bits(enable, len)),
+                Op::RunTestIdle(1),
+                Op::WriteIr(opcode_bits(isc_dna, len)),
+                Op::ReadDr(57),
+                Op::WriteIr(opcode_bits(disable, len)),
+                Op::RunTestIdle(1),
+                Op::TestLogicReset,
+            ]);
+        }
+    }
+
+    #[test]
+    fn spartan6_bitstream() {
+        let data = [
+            0xFF, 0xFF, 0xAA, 0x99, 0x55, 0x66, 0x20, 0x00,
+            0x31, 0xC2, 0x04, 0x00, 0x10, 0x93, 0x20, 0x00,
+        ];
+        let mut bitstream = Bitstream::from_data(&data);
+        assert_eq!(bitstream.idcode(), Some(0x0400_1093));
+        assert!(X7::new(MockTap::new(), X7IDCODE::X6SLX9).check_bitstream(&mut bitstream, false).is_ok());
+        let x7 = X7::new(MockTap::new(), X7IDCODE::X6SLX16);
+        assert!(matches!(x7.check_bitstream(&mut bitstream, false),
+                         Err(Error::IncompatibleIdcode { bitstream: 0x0400_1093, .. })));
+        assert!(matches!(bitstream.set_idcode(0x0400_2093), Err(Error::BitstreamParse(_))));
+        assert_eq!(bitstream.data(), data);
+    }
+
+    #[test]
+    fn program() {
+        // INIT_B and INIT_COMPLETE before programming, then also DONE and RELEASE_DONE.
+        let mut tap = MockTap::new();
+        push_status(&mut tap, 0x0000_1800);
+        push_status(&mut tap, 0x0010_7DFC);
+        let bitstream = [0xAA, 0x99, 0x55, 0x66, 0x01, 0x80];
+        let mut x7 = X7::new(tap, X7IDCODE::X7A35T);
+        x7.program(&bitstream).unwrap();
+
+        let mut ops = status_ops();
+        ops.extend([
+            Op::TestLogicReset,
+            Op::WriteIr(ir(Command::JPROGRAM)),
+            Op::RunTestIdle(1),
+            Op::Sleep(Duration::from_millis(20)),
+            Op::TestLogicReset,
+            Op::WriteIr(ir(Command::CFG_IN)),
+            // Each byte is shifted most significant bit first.
+            Op::WriteDr(bitstream.iter().flat_map(|b| (0..8).rev().map(move |i| (b >> i) & 1 == 1)).collect()),
+            Op::RunTestIdle(1),
+            Op::WriteIr(ir(Command::JSTART)),
+            Op::RunTestIdle(2000),
+            Op::TestLogicReset,
+        ]);
+        ops.extend(status_ops());
+        ops.push(Op::TestLogicReset);
+        assert_eq!(x7.tap().ops(), ops);
+        assert_eq!(x7.tap().pending_responses(), 0);
+    }
+
+    #[test]
+    fn slr_padding() {
+        // Four SLRs with SLR 1 as master, so one bypass bit before it and two after.
+        let idcode = X7IDCODE::X7VX1140T;
+        let bypass = ir(Command::BYPASS);
+        let mut tap = MockTap::new();
+        let dna = 0x0123_4567_89AB_CDEFu64;
+        let mut dr = vec![true];
+        dr.extend((0..64).map(|i| (dna >> i) & 1 == 1));
+        dr.extend([true, true]);
+        tap.push_dr(dr);
+        let mut x7 = X7::new(tap, idcode);
+        assert_eq!(x7.slr(), 1);
+        assert_eq!(x7.dna().unwrap(), dna.to_le_bytes());
+        let dna_ir = [bypass.clone(), ir(Command::FUSE_DNA), bypass.clone(), bypass.clone()].concat();
+        assert_eq!(x7.tap().ops(), [Op::WriteIr(dna_ir), Op::ReadDr(67)]);
+
+        // Data for SLR 3 has three bypass bits before it and none after.
+        x7.tap_mut().clear();
+        x7.select_slr(3).unwrap();
+        let mut captured = vec![false; 3];
+        captured.extend([true; 8]);
+        x7.tap_mut().push_dr(captured);
+        assert_eq!(x7.user_dr(1, &[true, false], 8).unwrap(), [true; 8]);
+        let user_ir = [bypass.clone(), bypass.clone(), bypass.clone(), ir(Command::USER1)].concat();
+        let mut dr = vec![false; 3];
+        dr.extend([true, false, false, false, false, false, false, false]);
+        assert_eq!(x7.tap().ops(), [Op::WriteIr(user_ir), Op::ExchangeDr(dr), Op::RunTestIdle(1)]);
+
+        // Broadcast commands go to every SLR.
+        x7.tap_mut().clear();
+        x7.highz().unwrap();
+        assert_eq!(x7.tap().ops()[0], Op::WriteIr(ir(Command::HIGHZ_IO).repeat(4)));
+
+        assert!(matches!(x7.select_slr(4), Err(Error::InvalidSlr(4, 4))));
+    }
+
+    #[test]
+    fn gtz_unsupported() {
+        let mut x7 = X7::new(MockTap::new(), X7IDCODE::X7VH580T);
+        assert!(matches!(x7.status(), Err(Error::GtzUnsupported("X7VH580T"))));
+        assert!(!x7.tap().ir_writes().any(|_| true));
+    }
+
+    #[test]
+    fn program_checks_status() {
+        // INIT_B low before programming.
+        let mut tap = MockTap::new();
+        push_status(&mut tap, 0x0000_0800);
+        let mut x7 = X7::new(tap, X7IDCODE::X7A35T);
+        assert!(matches!(x7.program(&[0; 4]), Err(Error::BadStatus)));
+        assert!(!x7.tap().ir_writes().any(|w| w == ir(Command::JPROGRAM)));
+
+        // DONE low after programming.
+        let mut tap = MockTap::new();
+        push_status(&mut tap, 0x0000_1800);
+        push_status(&mut tap, 0x0000_1800);
+        let mut x7 = X7::new(tap, X7IDCODE::X7A35T);
+        assert!(matches!(x7.program(&[0; 4]), Err(Error::BadStatus)));
+
+        // ID error after programming.
+        let mut tap = MockTap::new();
+        push_status(&mut tap, 0x0000_1800);
+        push_status(&mut tap, 0x0000_F800);
+        let mut x7 = X7::new(tap, X7IDCODE::X7A35T);
+        assert!(matches!(x7.program(&[0; 4]), Err(Error::BadStatus)));
+    }
 }
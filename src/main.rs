@@ -1,18 +1,25 @@
 // Copyright 2025 Adam Greig
 // Licensed under Apache-2.0 and MIT licenses.
 
-use std::{time::{Instant, Duration}};
-use clap::{Command, Arg, ArgAction, crate_description, crate_version, value_parser};
-use clap_num::si_number;
-use anyhow::bail;
+use std::{time::{Instant, Duration}, net::TcpListener, collections::BTreeMap};
+use std::{io::{BufWriter, Read, Write}, fs::File, sync::mpsc, thread};
+use clap::{Command, Arg, ArgAction, ArgMatches, crate_description, crate_version, value_parser};
+use clap_num::{si_number, maybe_hex};
+use anyhow::{anyhow, bail};
+use serde_json::json;
+use indicatif::{MultiProgress, ProgressBar};
 
 use jtagdap::probe::{Probe, ProbeInfo};
 use jtagdap::dap::DAP;
-use jtagdap::jtag::{JTAG, JTAGChain};
-use x7dap::{check_tap_idx, auto_tap_idx, X7IDCODE, X7, Bitstream};
+use jtagdap::jtag::{JTAG, JTAGChain, IDCODE};
+use x7dap::{check_tap_idx, auto_tap_idx, scan_chain, Compatibility, DeviceId, X7IDCODE, X7, Bitstream};
+use x7dap::{program_progress_bar, finish_program_progress_bar};
+use x7dap::{svf::{self, Player, Svf, SvfWriter}, tap::Tap, taps, xsvf::Xsvf};
+use x7dap::{axi::AxiBridge, uart::JtagUart, bsdl::Bsdl, bscan::BoundaryScan, interconnect::Netlist};
 
-fn main() -> anyhow::Result<()> {
-    let matches = Command::new("x7dap")
+/// Command-line interface definition.
+fn cli() -> Command {
+    Command::new("x7dap")
         .version(crate_version!())
         .about(crate_description!())
         .subcommand_required(true)
@@ -32,6 +39,11 @@ fn main() -> anyhow::Result<()> {
              .action(ArgAction::Count)
              .conflicts_with("quiet")
              .global(true))
+        .arg(Arg::new("json")
+             .help("Print results as JSON instead of formatted text")
+             .long("json")
+             .action(ArgAction::SetTrue)
+             .global(true))
         .arg(Arg::new("probe")
              .help("VID:PID[:SN] of CMSIS-DAP device to use")
              .long("probe")
@@ -53,6 +65,12 @@ fn main() -> anyhow::Result<()> {
              .action(ArgAction::Set)
              .value_parser(value_parser!(usize))
              .global(true))
+        .arg(Arg::new("slr")
+             .help("SLR to access on devices with several SLRs (0-indexed)")
+             .long("slr")
+             .action(ArgAction::Set)
+             .value_parser(value_parser!(usize))
+             .global(true))
         .arg(Arg::new("ir-lengths")
              .help("Lengths of each IR, starting from TAP 0, comma-separated")
              .long("ir-lengths")
@@ -69,6 +87,23 @@ fn main() -> anyhow::Result<()> {
              .default_value("192")
              .value_parser(value_parser!(usize))
              .global(true))
+        .arg(Arg::new("record")
+             .help("Record device operations to an SVF file")
+             .long("record")
+             .action(ArgAction::Set)
+             .global(true))
+        .arg(Arg::new("record-only")
+             .help("Only record operations for --device, without using a probe")
+             .long("record-only")
+             .action(ArgAction::SetTrue)
+             .requires("record")
+             .requires("device")
+             .global(true))
+        .arg(Arg::new("device")
+             .help("Device to record for with --record-only, such as XC7A35T")
+             .long("device")
+             .action(ArgAction::Set)
+             .global(true))
         .subcommand(Command::new("probes")
             .about("List available CMSIS-DAP probes"))
         .subcommand(Command::new("scan")
@@ -82,16 +117,162 @@ fn main() -> anyhow::Result<()> {
         .subcommand(Command::new("status")
             .about("Read the device status register"))
         .subcommand(Command::new("xadc")
-            .about("Read the XADC values"))
+            .about("Read the XADC values")
+            .subcommand(Command::new("serve")
+                .about("Serve XADC, status, and DNA as OpenMetrics over HTTP")
+                .arg(Arg::new("listen")
+                     .help("Address to listen on")
+                     .long("listen")
+                     .action(ArgAction::Set)
+                     .default_value("127.0.0.1:9100"))))
+        .subcommand(Command::new("highz")
+            .about("Float all I/O pins until Enter is pressed"))
+        .subcommand(Command::new("shutdown")
+            .about("Halt the running design using the shutdown sequence"))
+        .subcommand(Command::new("startup")
+            .about("Restart a halted design using the startup sequence"))
+        .subcommand(Command::new("user")
+            .about("Shift data through a USER1 to USER4 data register")
+            .arg(Arg::new("register")
+                 .help("USER register number, 1 to 4")
+                 .required(true)
+                 .value_parser(value_parser!(u8).range(1..=4)))
+            .arg(Arg::new("length")
+                 .help("Number of bits to shift each time")
+                 .long("len")
+                 .required(true)
+                 .value_parser(value_parser!(usize)))
+            .arg(Arg::new("data")
+                 .help("Hex values to shift in turn, least significant bit first, defaults to 0")
+                 .num_args(0..)))
+        .subcommand(Command::new("axi")
+            .about("Access AXI4-Lite registers through a JTAG-to-AXI bridge")
+            .subcommand_required(true)
+            .arg(Arg::new("user")
+                 .help("USER register the bridge is connected to")
+                 .long("user")
+                 .action(ArgAction::Set)
+                 .value_parser(value_parser!(u8).range(1..=4))
+                 .default_value("1")
+                 .global(true))
+            .subcommand(Command::new("read")
+                .about("Read 32-bit words")
+                .arg(Arg::new("address")
+                     .help("Address to read from")
+                     .required(true)
+                     .value_parser(maybe_hex::<u32>))
+                .arg(Arg::new("count")
+                     .help("Number of consecutive words to read")
+                     .value_parser(maybe_hex::<u32>)
+                     .default_value("1")))
+            .subcommand(Command::new("write")
+                .about("Write 32-bit words")
+                .arg(Arg::new("address")
+                     .help("Address to write to")
+                     .required(true)
+                     .value_parser(maybe_hex::<u32>))
+                .arg(Arg::new("data")
+                     .help("Words to write to consecutive addresses")
+                     .num_args(1..)
+                     .required(true)
+                     .value_parser(maybe_hex::<u32>))))
+        .subcommand(Command::new("uart")
+            .about("Interactive console through a JTAG UART bridge")
+            .arg(Arg::new("user")
+                 .help("USER register the bridge is connected to")
+                 .long("user")
+                 .action(ArgAction::Set)
+                 .value_parser(value_parser!(u8).range(1..=4))
+                 .default_value("3"))
+            .arg(Arg::new("log")
+                 .help("File to also write received data to")
+                 .long("log")
+                 .action(ArgAction::Set)))
+        .subcommand(Command::new("xvc")
+            .about("Serve the JTAG chain to Vivado using Xilinx Virtual Cable")
+            .arg(Arg::new("listen")
+                 .help("Address to listen on")
+                 .long("listen")
+                 .action(ArgAction::Set)
+                 .default_value("127.0.0.1:2542")))
+        .subcommand(Command::new("svf")
+            .about("Serial Vector Format files")
+            .subcommand_required(true)
+            .subcommand(Command::new("play")
+                .about("Play an SVF or XSVF file on the JTAG chain")
+                .arg(Arg::new("file")
+                     .help("SVF or XSVF file to play")
+                     .required(true))
+                .arg(Arg::new("keep-going")
+                     .help("Continue after TDO mismatches")
+                     .long("keep-going")
+                     .action(ArgAction::SetTrue))))
+        .subcommand(Command::new("bscan")
+            .about("Boundary-scan pin access")
+            .subcommand_required(true)
+            .arg(Arg::new("bsdl")
+                 .help("BSDL file or compact map, if not found automatically")
+                 .long("bsdl")
+                 .short('b')
+                 .action(ArgAction::Set)
+                 .global(true))
+            .arg(Arg::new("package")
+                 .help("Device package, such as CSG324")
+                 .long("package")
+                 .action(ArgAction::Set)
+                 .global(true))
+            .subcommand(Command::new("interconnect")
+                .about("Test board connections between pins for opens and shorts")
+                .arg(Arg::new("netlist")
+                     .help("Netlist file listing the pins on each net")
+                     .required(true)))
+            .subcommand(Command::new("export")
+                .about("Convert a BSDL file to a compact boundary-scan map"))
+            .subcommand(Command::new("sample")
+                .about("Sample pin states without affecting device operation")
+                .arg(Arg::new("pins")
+                     .help("Pins to report, defaults to all pins")
+                     .num_args(0..)))
+            .subcommand(Command::new("drive")
+                .about("Drive pins using EXTEST until Enter is pressed")
+                .arg(Arg::new("pins")
+                     .help("Pin values to drive, as PIN=0, PIN=1, or PIN=Z")
+                     .num_args(1..)
+                     .required(true))))
         .subcommand(Command::new("program")
             .about("Program SRAM with bitstream")
             .arg(Arg::new("file")
-                 .help("File to program to device")
-                 .required(true)))
-        .get_matches();
+                 .help("File to program to device, or TAP=FILE to program several devices. \
+                        Files given as TAP=FILE are assigned first, and other files go to the \
+                        first unassigned device matching their IDCODE. Prefix a path such as \
+                        1=x.bit with ./ to treat it as a file.")
+                 .num_args(1..)
+                 .required(true))
+            .arg(Arg::new("warn-revision")
+                 .help("Warn if the bitstream targets a different silicon revision to the device")
+                 .long("warn-revision")
+                 .action(ArgAction::SetTrue))
+            .arg(Arg::new("same-die")
+                 .help("Allow bitstreams for other devices built on the same die")
+                 .long("same-die")
+                 .action(ArgAction::SetTrue))
+            .arg(Arg::new("compatible")
+                 .help("Comma-separated group of devices whose bitstreams are interchangeable")
+                 .long("compatible")
+                 .action(ArgAction::Append))
+            .arg(Arg::new("all-probes")
+                 .help("Program the device on every probe, or every probe matching --probe, in parallel")
+                 .long("all-probes")
+                 .action(ArgAction::SetTrue)
+                 .conflicts_with_all(["record", "record-only"])))
+}
+
+fn main() -> anyhow::Result<()> {
+    let matches = cli().get_matches();
 
     let t0 = Instant::now();
     let quiet = matches.get_flag("quiet");
+    let json = matches.get_flag("json");
     let verbose = matches.get_count("verbose");
     let env = if quiet {
         env_logger::Env::default().default_filter_or("error")
@@ -106,13 +287,64 @@ fn main() -> anyhow::Result<()> {
     };
     env_logger::Builder::from_env(env).format_timestamp(None).init();
 
+    // Informative output is suppressed in JSON mode so stdout only contains the result.
+    let chatty = !quiet && !json;
+
     // Listing probes does not require first connecting to a probe,
     // so we just list them and quit early.
     if matches.subcommand_name().unwrap() == "probes" {
-        print_probe_list();
+        print_probe_list(json);
+        return Ok(());
+    }
+
+    // Exporting a boundary-scan map only requires the BSDL file.
+    if let Some(("bscan", matches)) = matches.subcommand()
+        && let Some(("export", _)) = matches.subcommand()
+    {
+        let Some(path) = matches.get_one::<String>("bsdl") else {
+            bail!("A BSDL file must be specified with --bsdl.");
+        };
+        let package = matches.get_one::<String>("package").map(|p| p.as_str());
+        let bscan = BoundaryScan::new(&Bsdl::from_path(path)?, package)?;
+        print!("{}", bscan.to_compact());
+        return Ok(());
+    }
+
+    // Recording without a probe only requires the device type.
+    if matches.get_flag("record-only") {
+        if !matches!(matches.subcommand_name(), Some("program" | "reload" | "shutdown" | "startup")) {
+            bail!("Only program, reload, shutdown, and startup can be used with --record-only.");
+        }
+        check_record_only_targets(&matches)?;
+        let name = matches.get_one::<String>("device").unwrap();
+        let Some(idcode) = X7IDCODE::try_from_name(name) else {
+            bail!("Unknown device {name}.");
+        };
+        let mut svf = SvfWriter::new(Box::new(BufWriter::new(
+            File::create(matches.get_one::<String>("record").unwrap())?)));
+        if let Some(ir_lens) = matches.get_many::<usize>("ir-lengths") {
+            let ir_lens: Vec<usize> = ir_lens.copied().collect();
+            let Some(&tap_idx) = matches.get_one::<usize>("tap") else {
+                bail!("--tap must be specified with --ir-lengths when recording.");
+            };
+            if tap_idx >= ir_lens.len() {
+                bail!("The provided tap index {tap_idx} is not in the provided IR lengths.");
+            }
+            svf = svf.with_chain(&ir_lens, tap_idx);
+        }
+        let mut x7 = X7::record_only(svf, idcode);
+        run_command(&mut x7, &matches, chatty, json, t0)?;
+        x7.finish_recording()?;
         return Ok(());
     }
 
+    // Programming every probe opens each probe on its own thread.
+    if let Some(("program", pmatches)) = matches.subcommand()
+        && pmatches.get_flag("all-probes")
+    {
+        return program_all_probes(&matches, chatty, json, t0);
+    }
+
     // All functions after this point require an open probe, so
     // we now attempt to connect to the specified probe.
     let probe = if let Some(probe) = matches.get_one::<String>("probe") {
@@ -123,11 +355,57 @@ fn main() -> anyhow::Result<()> {
 
     // Create a JTAG interface using the probe.
     let dap = DAP::new(probe)?;
+
+    // SVF files and the XVC server drive the whole chain directly using the DAP.
+    if let Some(("svf", svf_matches)) = matches.subcommand() {
+        if let Some(&freq) = matches.get_one::<u32>("freq") {
+            dap.set_clock(freq)?;
+        }
+        let svf_matches = svf_matches.subcommand_matches("play").unwrap();
+        let path = svf_matches.get_one::<String>("file").unwrap();
+        let mut player = Player::new(&dap).keep_going(svf_matches.get_flag("keep-going"));
+        let mismatches = if path.to_ascii_lowercase().ends_with(".xsvf") {
+            let xsvf = Xsvf::from_path(path)?;
+            if chatty { println!("Playing {} XSVF commands from {path}...", xsvf.len()) };
+            player.play_xsvf(&xsvf)?
+        } else {
+            let svf = Svf::from_path(path)?;
+            if chatty { println!("Playing {} SVF commands from {path}...", svf.len()) };
+            player.play(&svf)?
+        };
+        if json {
+            println!("{}", json!({ "mismatches": mismatches }));
+        } else {
+            for mismatch in &mismatches {
+                println!("Mismatch at {mismatch}");
+            }
+        }
+        if !mismatches.is_empty() {
+            bail!("{} TDO mismatch{} found", mismatches.len(),
+                  if mismatches.len() == 1 { "" } else { "es" });
+        }
+        if chatty {
+            let t1 = t0.elapsed();
+            println!("Finished in {}.{:02}s", t1.as_secs(), t1.subsec_millis()/10);
+        }
+        return Ok(());
+    }
+
+    if let Some(("xvc", xvc_matches)) = matches.subcommand() {
+        if let Some(&freq) = matches.get_one::<u32>("freq") {
+            dap.set_clock(freq)?;
+        }
+        let listen = xvc_matches.get_one::<String>("listen").unwrap();
+        let listener = TcpListener::bind(listen)?;
+        if chatty { println!("Serving XVC on {listen}") };
+        return Ok(x7dap::xvc::serve(&dap, listener)?);
+    }
+
     let mut jtag = JTAG::new(dap);
 
     // At this point we can handle the reset command.
     if matches.subcommand_name().unwrap() == "reset" {
-        if !quiet { println!("Pulsing nRST line.") };
+        if chatty { println!("Pulsing nRST line.") };
         return Ok(jtag.pulse_nrst(Duration::from_millis(100))?);
     }
 
@@ -147,17 +425,23 @@ fn main() -> anyhow::Result<()> {
         .map(|lens| lens.copied().collect::<Vec<usize>>());
 
     // Scan the JTAG chain to detect all available TAPs.
-    // TODO: This might fail on common Zynq parts with two TAPs but ambiguous IRLENs;
-    // if there are only two TAPs detected the IRLENs are always (6, 4) so we should
-    // set them to avoid the user having to do it.
-    let chain = jtag.scan(ir_lens.as_deref())?;
+    let chain = scan_chain(&mut jtag, ir_lens.as_deref())?;
 
     // At this point we can handle the 'scan' command.
     if matches.subcommand_name().unwrap() == "scan" {
-        print_jtag_chain(&chain);
+        print_jtag_chain(&chain, json);
         return Ok(());
     }
 
+    // Programming several bitstreams handles each device on the chain in turn.
+    let targets = program_targets(&matches);
+    if targets.len() > 1 || targets.iter().any(|(tap, _)| tap.is_some()) {
+        if matches.get_one::<String>("record").is_some() {
+            bail!("Recording is not supported when programming several devices.");
+        }
+        return program_chain(jtag, chain, &targets, &matches, chatty, json, t0);
+    }
+
     // If the user specified a TAP, we'll use it, but otherwise
     // attempt to find a single FPGA in the scan chain.
     let (tap_idx, idcode) = if let Some(&tap_idx) = matches.get_one("tap") {
@@ -165,65 +449,554 @@ fn main() -> anyhow::Result<()> {
             log::debug!("Provided tap index is a 7-series device");
             (tap_idx, idcode)
         } else {
-            print_jtag_chain(&chain);
+            print_jtag_chain(&chain, json);
             bail!("The provided tap index {tap_idx} does not have an 7-series IDCODE.");
         }
     } else if let Some((index, idcode)) = auto_tap_idx(&chain) {
         (index, idcode)
     } else {
-        print_jtag_chain(&chain);
+        print_jtag_chain(&chain, json);
         bail!("Could not find an 7-series IDCODE in the JTAG chain.");
     };
 
     // Create a TAP instance, consuming the JTAG instance.
+    let chain_irlens = chain.irlens().to_vec();
+    let revision = chain.idcodes()[tap_idx].map(|id| id.version()).unwrap_or(0);
     let tap = jtag.into_tap(chain, tap_idx)?;
 
-    let mut x7 = X7::new(tap, idcode);
+    let mut x7 = X7::new(tap, idcode).with_revision(revision);
+
+    // If the user asked to record operations, start recording now.
+    if let Some(path) = matches.get_one::<String>("record") {
+        let svf = SvfWriter::new(Box::new(BufWriter::new(File::create(path)?)))
+            .with_chain(chain_irlens.as_slice(), tap_idx);
+        x7.record(svf);
+    }
+
+    run_command(&mut x7, &matches, chatty, json, t0)?;
+    x7.finish_recording()?;
+    Ok(())
+}
+
+/// Run a device command on `x7`.
+fn run_command<T: Tap>(x7: &mut X7<T>, matches: &ArgMatches, chatty: bool, json: bool, t0: Instant)
+    -> anyhow::Result<()>
+{
+    let slr = matches.get_one::<usize>("slr").copied();
+    if let Some(slr) = slr {
+        x7.select_slr(slr)?;
+    }
 
     match matches.subcommand_name() {
         Some("dna") => {
-            if !quiet { println!("Reading DNA...") };
+            if chatty { println!("Reading DNA...") };
             let dna = x7.dna()?;
-            println!("DNA: {}", dna.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(""));
+            let dna = dna.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join("");
+            if json {
+                println!("{}", json!({ "dna": dna }));
+            } else {
+                println!("DNA: {dna}");
+            }
         },
         Some("status") => {
-            if !quiet { println!("Reading status...") };
-            let status = x7.status()?;
-            println!("{status:?}");
+            if chatty { println!("Reading status...") };
+            if !json {
+                println!("Device: {}", x7.device_id());
+            }
+            if x7.slr_count() > 1 && slr.is_none() {
+                let statuses = x7.status_all()?;
+                if json {
+                    println!("{}", serde_json::to_string(&statuses)?);
+                } else {
+                    for (slr, status) in statuses.iter().enumerate() {
+                        println!("SLR {slr}:\n{status:?}");
+                    }
+                }
+            } else {
+                let status = x7.status()?;
+                if json {
+                    println!("{}", serde_json::to_string(&status)?);
+                } else {
+                    println!("{status:?}");
+                }
+            }
         },
         Some("xadc") => {
-            if !quiet { println!("Reading XADC...") };
-            let xadc = x7.xadc()?;
-            println!("{xadc}");
+            let matches = matches.subcommand_matches("xadc").unwrap();
+            if let Some(matches) = matches.subcommand_matches("serve") {
+                let listen = matches.get_one::<String>("listen").unwrap();
+                let listener = TcpListener::bind(listen)?;
+                if chatty { println!("Serving metrics on http://{listen}/metrics") };
+                x7dap::metrics::serve(x7, listener)?;
+            } else {
+                if chatty { println!("Reading XADC...") };
+                let xadc = x7.xadc()?;
+                if json {
+                    println!("{}", serde_json::to_string(&xadc)?);
+                } else {
+                    println!("{xadc}");
+                }
+            }
         },
         Some("reload") => {
-            if !quiet { println!("Reloading configuration...") };
+            if chatty { println!("Reloading configuration...") };
             x7.jprogram()?;
         }
+        Some("highz") => {
+            x7.highz()?;
+            if chatty { println!("I/O pins floating, press Enter to release.") };
+            std::io::stdin().read_line(&mut String::new())?;
+            x7.test_logic_reset()?;
+        },
+        Some("shutdown") => {
+            if chatty { println!("Shutting down design...") };
+            x7.shutdown()?;
+        },
+        Some("startup") => {
+            if chatty { println!("Starting up design...") };
+            x7.startup()?;
+        },
+        Some("user") => {
+            let matches = matches.subcommand_matches("user").unwrap();
+            let n = *matches.get_one::<u8>("register").unwrap();
+            let len = *matches.get_one::<usize>("length").unwrap();
+            let data: Vec<&str> = match matches.get_many::<String>("data") {
+                Some(data) => data.map(|d| d.as_str()).collect(),
+                None => vec!["0"],
+            };
+            let mut shifts = Vec::new();
+            for data in data {
+                let bits_in = hex_to_bits(data, len)?;
+                let bits_out = x7.user_dr(n, &bits_in, len)?;
+                let (data_in, data_out) = (svf::bits_to_hex(&bits_in), svf::bits_to_hex(&bits_out));
+                if json {
+                    shifts.push(json!({ "in": data_in, "out": data_out }));
+                } else if chatty {
+                    println!("USER{n}: {data_in} -> {data_out}");
+                } else {
+                    println!("{data_out}");
+                }
+            }
+            if json {
+                println!("{}", json!({ "register": n, "length": len, "shifts": shifts }));
+            }
+        },
+        Some("axi") => {
+            let matches = matches.subcommand_matches("axi").unwrap();
+            let axi = AxiBridge::new(*matches.get_one::<u8>("user").unwrap());
+            match matches.subcommand() {
+                Some(("read", matches)) => {
+                    let addr = *matches.get_one::<u32>("address").unwrap();
+                    let count = *matches.get_one::<u32>("count").unwrap();
+                    let mut words = Vec::new();
+                    for idx in 0..count {
+                        let addr = addr.wrapping_add(idx * 4);
+                        let data = axi.read32(x7, addr)?;
+                        if json {
+                            words.push(json!({ "address": addr, "data": data }));
+                        } else {
+                            println!("0x{addr:08X}: 0x{data:08X}");
+                        }
+                    }
+                    if json {
+                        println!("{}", json!({ "words": words }));
+                    }
+                },
+                Some(("write", matches)) => {
+                    let addr = *matches.get_one::<u32>("address").unwrap();
+                    for (idx, data) in matches.get_many::<u32>("data").unwrap().enumerate() {
+                        let addr = addr.wrapping_add(idx as u32 * 4);
+                        if chatty { println!("0x{addr:08X} <- 0x{data:08X}") };
+                        axi.write32(x7, addr, *data)?;
+                    }
+                },
+                _ => unreachable!(),
+            }
+        },
+        Some("uart") => {
+            let matches = matches.subcommand_matches("uart").unwrap();
+            let uart = JtagUart::new(*matches.get_one::<u8>("user").unwrap());
+            let log = matches.get_one::<String>("log").map(File::create).transpose()?;
+            if chatty { println!("Connected to JTAG UART, press Ctrl-] to exit.") };
+            run_uart_console(x7, uart, log)?;
+        },
+        Some("bscan") => {
+            let matches = matches.subcommand_matches("bscan").unwrap();
+            let package = matches.get_one::<String>("package").map(|p| p.as_str());
+            let mut bscan = match matches.get_one::<String>("bsdl") {
+                Some(path) if path.ends_with(".bsr") => {
+                    BoundaryScan::from_compact(&std::fs::read_to_string(path)?)?
+                        .check_device(x7.idcode())?
+                },
+                Some(path) => {
+                    let bsdl = Bsdl::from_path(path)?;
+                    if !bsdl.matches_idcode(x7.idcode().into()) {
+                        bail!(x7dap::Error::BsdlIdcodeMismatch(x7.idcode() as u32));
+                    }
+                    BoundaryScan::new(&bsdl, package)?
+                },
+                None => BoundaryScan::for_device(x7.idcode(), package)?,
+            };
+            match matches.subcommand() {
+                Some(("sample", matches)) => {
+                    let pins: Vec<&String> = matches.get_many("pins").unwrap_or_default().collect();
+                    let states = bscan.sample(x7)?;
+                    let states: BTreeMap<&str, bool> = if pins.is_empty() {
+                        states.iter().map(|(pin, state)| (pin.as_str(), *state)).collect()
+                    } else {
+                        let mut selected = BTreeMap::new();
+                        for pin in pins {
+                            let pin = bscan.pin(pin)?;
+                            let Some(&state) = states.get(&pin.name) else {
+                                bail!(x7dap::Error::PinNotSampleable(pin.name.clone()));
+                            };
+                            selected.insert(pin.name.as_str(), state);
+                        }
+                        selected
+                    };
+                    print_pin_states(&bscan, &states, json)?;
+                },
+                Some(("drive", matches)) => {
+                    for spec in matches.get_many::<String>("pins").unwrap() {
+                        let Some((pin, value)) = spec.split_once('=') else {
+                            bail!("Invalid pin specification {spec}, expected PIN=VALUE");
+                        };
+                        bscan.set(pin, value.parse()?)?;
+                    }
+                    let states = bscan.apply(x7)?;
+                    let states = states.iter().map(|(pin, state)| (pin.as_str(), *state)).collect();
+                    print_pin_states(&bscan, &states, json)?;
+                    if chatty { println!("Driving pins, press Enter to release.") };
+                    std::io::stdin().read_line(&mut String::new())?;
+                    bscan.release(x7)?;
+                },
+                Some(("interconnect", matches)) => {
+                    let netlist = Netlist::from_path(matches.get_one::<String>("netlist").unwrap())?;
+                    if chatty { println!("Testing {} nets...", netlist.nets().len()) };
+                    let faults = netlist.test(&mut bscan, x7)?;
+                    if json {
+                        println!("{}", json!({ "faults": faults }));
+                    } else if faults.is_empty() {
+                        println!("No faults found.");
+                    } else {
+                        for fault in &faults {
+                            println!(" - {fault}");
+                        }
+                    }
+                    if !faults.is_empty() {
+                        bail!("Interconnect test found {} fault(s).", faults.len());
+                    }
+                },
+                _ => panic!("Unhandled command."),
+            }
+        },
         Some("program") => {
+            let targets = program_targets(matches);
+            let [(None, path)] = &targets[..] else {
+                bail!("Only one bitstream, without a TAP index, can be programmed into a single device.");
+            };
             let matches = matches.subcommand_matches("program").unwrap();
-            let path = matches.get_one::<String>("file").unwrap();
-            let bitstream = Bitstream::from_path(path)?;
-            if quiet {
-                x7.program(bitstream.data())?;
-            } else {
+            let mut bitstream = Bitstream::from_path(path)?;
+            x7.set_compatibility(program_compatibility(matches)?);
+            x7.check_bitstream(&mut bitstream, matches.get_flag("warn-revision"))?;
+            if chatty {
                 x7.program_progress(bitstream.data())?;
+            } else {
+                x7.program(bitstream.data())?;
+            }
+            if json {
+                println!("{}", json!({
+                    "file": path,
+                    "bytes": bitstream.data().len(),
+                    "elapsed": t0.elapsed().as_secs_f64(),
+                }));
             }
         }
         _ => panic!("Unhandled command."),
     }
 
     let t1 = t0.elapsed();
-    if !quiet {
+    if chatty {
         println!("Finished in {}.{:02}s", t1.as_secs(), t1.subsec_millis()/10);
     }
 
     Ok(())
 }
 
-fn print_probe_list() {
-    let probes = ProbeInfo::list();
+/// Bitstreams given to `program`, each with the TAP index given as TAP=FILE if any.
+///
+/// Returns an empty list for other commands.
+fn program_targets(matches: &ArgMatches) -> Vec<(Option<usize>, String)> {
+    let Some(matches) = matches.subcommand_matches("program") else {
+        return Vec::new();
+    };
+    matches.get_many::<String>("file").unwrap_or_default().map(|file| {
+        match file.split_once('=').and_then(|(tap, path)| Some((tap.parse().ok()?, path))) {
+            Some((tap, path)) => (Some(tap), path.to_string()),
+            None => (None, file.to_string()),
+        }
+    }).collect()
+}
+
+/// Check `program` is given a single bitstream without a TAP index for `--record-only`.
+///
+/// The recorded device is selected with `--tap` and `--ir-lengths` instead.
+fn check_record_only_targets(matches: &ArgMatches) -> anyhow::Result<()> {
+    match &program_targets(matches)[..] {
+        [] | [(None, _)] => Ok(()),
+        [(Some(tap), _)] =>
+            bail!("TAP index {tap} cannot be given with --record-only; select the device with --tap and --ir-lengths."),
+        _ => bail!("Only one bitstream can be programmed with --record-only."),
+    }
+}
+
+/// Bitstream compatibility groups given to `program`.
+fn program_compatibility(matches: &ArgMatches) -> anyhow::Result<Compatibility> {
+    let mut compatibility = if matches.get_flag("same-die") {
+        Compatibility::same_die()
+    } else {
+        Compatibility::strict()
+    };
+    for group in matches.get_many::<String>("compatible").unwrap_or_default() {
+        compatibility = compatibility.with_group(&Compatibility::parse_group(group)?);
+    }
+    Ok(compatibility)
+}
+
+/// Assign each of `targets` to a device in a chain with `idcodes`.
+///
+/// Targets with a TAP index are assigned first. The others are then matched
+/// in chain order to the first unassigned device compatible with the IDCODE
+/// of their bitstream, given in `bitstream_idcodes`.
+fn assign_targets(
+    idcodes: &[Option<IDCODE>], targets: &[(Option<usize>, String)],
+    bitstream_idcodes: &[Option<u32>], compatibility: &Compatibility,
+) -> anyhow::Result<Vec<(usize, X7IDCODE)>> {
+    let device = |idx: usize| idcodes.get(idx).copied().flatten().and_then(X7IDCODE::try_from_idcode);
+    let mut assigned: Vec<Option<(usize, X7IDCODE)>> = vec![None; targets.len()];
+    for (i, (tap, _)) in targets.iter().enumerate() {
+        let Some(tap_idx) = *tap else { continue };
+        let Some(idcode) = device(tap_idx) else {
+            bail!("The provided tap index {tap_idx} does not have an 7-series IDCODE.");
+        };
+        if assigned.iter().flatten().any(|(idx, _)| *idx == tap_idx) {
+            bail!("TAP {tap_idx} has more than one bitstream to program.");
+        }
+        assigned[i] = Some((tap_idx, idcode));
+    }
+    for (i, (_, path)) in targets.iter().enumerate() {
+        if assigned[i].is_some() {
+            continue;
+        }
+        let Some(target) = bitstream_idcodes[i].and_then(X7IDCODE::try_from_u32) else {
+            bail!("Cannot find the device {path} is for; specify it as TAP={path}.");
+        };
+        let found = (0..idcodes.len()).find_map(|idx| {
+            let idcode = device(idx)?;
+            let free = !assigned.iter().flatten().any(|(assigned, _)| *assigned == idx);
+            (free && compatibility.compatible(idcode, target)).then_some((idx, idcode))
+        });
+        let Some(found) = found else {
+            bail!("No unassigned {} found in the JTAG chain for {path}.", target.name());
+        };
+        assigned[i] = Some(found);
+    }
+    Ok(assigned.into_iter().flatten().collect())
+}
+
+/// Program each of `targets` into a 7-series device on the chain in turn.
+///
+/// See `assign_targets` for how bitstreams are matched to devices. All other
+/// devices are left in BYPASS while each device is programmed.
+fn program_chain(
+    mut jtag: JTAG, mut chain: JTAGChain, targets: &[(Option<usize>, String)],
+    matches: &ArgMatches, chatty: bool, json: bool, t0: Instant,
+) -> anyhow::Result<()> {
+    let pmatches = matches.subcommand_matches("program").unwrap();
+    let compatibility = program_compatibility(pmatches)?;
+
+    // Work out which device each bitstream is for before programming any.
+    let bitstreams = targets.iter().map(|(_, path)| Bitstream::from_path(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let bitstream_idcodes: Vec<Option<u32>> = bitstreams.iter().map(Bitstream::idcode).collect();
+    let assigned = assign_targets(chain.idcodes(), targets, &bitstream_idcodes, &compatibility)
+        .inspect_err(|_| print_jtag_chain(&chain, json))?;
+
+    let mut results = Vec::new();
+    for (((tap_idx, idcode), (_, path)), mut bitstream) in assigned.into_iter().zip(targets).zip(bitstreams) {
+        let revision = chain.idcodes()[tap_idx].map(|id| id.version()).unwrap_or(0);
+        let tap = jtag.into_tap(chain, tap_idx)?;
+        let mut x7 = X7::new(tap, idcode).with_revision(revision);
+        x7.set_compatibility(compatibility.clone());
+        let result = x7.check_bitstream(&mut bitstream, pmatches.get_flag("warn-revision"))
+            .and_then(|_| {
+                if chatty {
+                    println!("Programming {path} to {} at TAP {tap_idx}", x7.device_id());
+                    x7.program_progress(bitstream.data())
+                } else {
+                    x7.program(bitstream.data())
+                }
+            });
+        (jtag, chain) = x7.into_tap().release();
+        result?;
+        results.push(json!({ "tap": tap_idx, "file": path, "bytes": bitstream.data().len() }));
+    }
+
+    let t1 = t0.elapsed();
+    if json {
+        println!("{}", json!({ "programmed": results, "elapsed": t1.as_secs_f64() }));
+    } else if chatty {
+        println!("Finished in {}.{:02}s", t1.as_secs(), t1.subsec_millis()/10);
+    }
+    Ok(())
+}
+
+/// Program the 7-series device on every matching probe in parallel, then print a summary.
+fn program_all_probes(matches: &ArgMatches, chatty: bool, json: bool, t0: Instant)
+    -> anyhow::Result<()>
+{
+    let targets = program_targets(matches);
+    let [(None, path)] = &targets[..] else {
+        bail!("Only one bitstream, without a TAP index, can be programmed with --all-probes.");
+    };
+    let bitstream = Bitstream::from_path(path)?;
+
+    let mut probes = ProbeInfo::list();
+    if let Some(spec) = matches.get_one::<String>("probe") {
+        let spec = ProbeInfo::from_specifier(spec)?;
+        probes.retain(|p| p.vid == spec.vid && p.pid == spec.pid && (spec.sn.is_none() || p.sn == spec.sn));
+    }
     if probes.is_empty() {
+        bail!("No matching CMSIS-DAP probes found.");
+    }
+    check_probe_serials(&probes)?;
+    if chatty { println!("Programming {path} using {} probes...", probes.len()) };
+
+    let multi = MultiProgress::new();
+    let results: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = probes.iter().map(|probe| {
+            let label = probe.sn.clone().unwrap_or_else(|| probe.to_string());
+            let pb = chatty.then(|| multi.add(program_progress_bar(bitstream.data().len(), label)));
+            let data = bitstream.data();
+            scope.spawn(move || {
+                let t = Instant::now();
+                let result = program_probe(probe, data, matches, pb.as_ref());
+                if let Some(pb) = pb {
+                    match &result {
+                        Ok(_) => finish_program_progress_bar(&pb),
+                        Err(_) => pb.abandon_with_message(format!("{} failed", pb.message())),
+                    }
+                }
+                (result, t.elapsed())
+            })
+        }).collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+
+    let failures = results.iter().filter(|(result, _)| result.is_err()).count();
+    if json {
+        let probes: Vec<_> = probes.iter().zip(&results).map(|(probe, (result, elapsed))| json!({
+            "name": probe.name,
+            "vid": probe.vid,
+            "pid": probe.pid,
+            "serial": probe.sn,
+            "device": result.as_ref().ok().map(|id| id.to_string()),
+            "error": result.as_ref().err().map(|e| format!("{e:#}")),
+            "elapsed": elapsed.as_secs_f64(),
+        })).collect();
+        println!("{}", json!({
+            "file": path,
+            "bytes": bitstream.data().len(),
+            "probes": probes,
+            "elapsed": t0.elapsed().as_secs_f64(),
+        }));
+    } else {
+        println!("{:<40} {:<16} {:>8}  Result", "Probe", "Device", "Time");
+        for (probe, (result, elapsed)) in probes.iter().zip(&results) {
+            let (device, outcome) = match result {
+                Ok(id) => (id.to_string(), "OK".to_string()),
+                Err(e) => ("-".to_string(), format!("Error: {e:#}")),
+            };
+            println!("{:<40} {:<16} {:>7.2}s  {outcome}",
+                     probe.to_string(), device, elapsed.as_secs_f64());
+        }
+        if chatty {
+            let t1 = t0.elapsed();
+            println!("Finished in {}.{:02}s", t1.as_secs(), t1.subsec_millis()/10);
+        }
+    }
+
+    if failures > 0 {
+        bail!("{failures} of {} probes failed to program", probes.len());
+    }
+    Ok(())
+}
+
+/// Check every probe in `probes` has a serial number, and that they are all different.
+///
+/// Probes are opened by serial number, so probes without one, or sharing one,
+/// could not be told apart and the same probe might be opened twice.
+fn check_probe_serials(probes: &[ProbeInfo]) -> anyhow::Result<()> {
+    for (i, probe) in probes.iter().enumerate() {
+        let Some(sn) = &probe.sn else {
+            bail!("Probe {probe} has no serial number, so cannot be used with --all-probes.");
+        };
+        if probes[..i].iter().any(|other| other.sn.as_ref() == Some(sn)) {
+            bail!("Several probes have serial number {sn}, so cannot be used with --all-probes.");
+        }
+    }
+    Ok(())
+}
+
+/// Open `probe`, find its 7-series device, and program `data` to it.
+fn program_probe(probe: &ProbeInfo, data: &[u8], matches: &ArgMatches, pb: Option<&ProgressBar>)
+    -> anyhow::Result<DeviceId>
+{
+    let pmatches = matches.subcommand_matches("program").unwrap();
+    let mut jtag = JTAG::new(DAP::new(probe.open()?)?);
+    if let Some(&freq) = matches.get_one::<u32>("freq") {
+        jtag.set_clock(freq)?;
+    }
+    if let Some(&max_length) = matches.get_one("scan-chain-length") {
+        jtag.set_max_length(max_length);
+    }
+    let ir_lens = matches
+        .get_many("ir-lengths")
+        .map(|lens| lens.copied().collect::<Vec<usize>>());
+    let chain = scan_chain(&mut jtag, ir_lens.as_deref())?;
+
+    let (tap_idx, idcode) = match matches.get_one::<usize>("tap") {
+        Some(&tap_idx) => check_tap_idx(&chain, tap_idx)
+            .map(|idcode| (tap_idx, idcode))
+            .ok_or_else(|| anyhow!("The provided tap index {tap_idx} does not have an 7-series IDCODE."))?,
+        None => auto_tap_idx(&chain)
+            .ok_or_else(|| anyhow!("Could not find an 7-series IDCODE in the JTAG chain."))?,
+    };
+    let revision = chain.idcodes()[tap_idx].map(|id| id.version()).unwrap_or(0);
+    let tap = jtag.into_tap(chain, tap_idx)?;
+    let mut x7 = X7::new(tap, idcode).with_revision(revision);
+    x7.set_compatibility(program_compatibility(pmatches)?);
+
+    let mut bitstream = Bitstream::from_data(data);
+    x7.check_bitstream(&mut bitstream, pmatches.get_flag("warn-revision"))?;
+    match pb {
+        Some(pb) => x7.program_cb(bitstream.data(), |n| pb.set_position(n as u64))?,
+        None => x7.program(bitstream.data())?,
+    }
+    Ok(x7.device_id())
+}
+
+fn print_probe_list(json: bool) {
+    let probes = ProbeInfo::list();
+    if json {
+        let probes: Vec<_> = probes.iter().map(|probe| json!({
+            "name": probe.name,
+            "vid": probe.vid,
+            "pid": probe.pid,
+            "serial": probe.sn,
+        })).collect();
+        println!("{}", json!({ "probes": probes }));
+    } else if probes.is_empty() {
         println!("No CMSIS-DAP probes found.");
     } else {
         println!("Found {} CMSIS-DAP probe{}:", probes.len(),
@@ -234,15 +1007,218 @@ fn print_probe_list() {
     }
 }
 
-fn print_jtag_chain(chain: &JTAGChain) {
-    println!("Detected JTAG chain, closest to TDO first:");
+/// Key which ends the UART console, Ctrl-].
+const UART_EXIT_KEY: u8 = 0x1d;
+
+/// Terminal switched to raw mode, restored to its previous mode on drop.
+#[cfg(unix)]
+struct RawTerminal {
+    saved: libc::termios,
+}
+
+#[cfg(unix)]
+impl RawTerminal {
+    /// Switch stdin to raw mode, if it is a terminal.
+    fn new() -> anyhow::Result<Option<Self>> {
+        use std::io::IsTerminal;
+        if !std::io::stdin().is_terminal() {
+            return Ok(None);
+        }
+        // SAFETY: termios is plain data, and is fully written by tcgetattr before use.
+        let mut saved: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut saved) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let mut raw = saved;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Some(RawTerminal { saved }))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.saved) };
+    }
+}
+
+/// Relay stdin to `uart` and received data to stdout and `log`,
+/// until stdin is closed or Ctrl-] is pressed.
+///
+/// On Unix, a terminal on stdin is put into raw mode for the duration, so
+/// keystrokes including Ctrl-C and Ctrl-D are sent to the design unaltered.
+fn run_uart_console<T: Tap>(x7: &mut X7<T>, uart: JtagUart, mut log: Option<File>) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    let _raw = RawTerminal::new()?;
+
+    // Read stdin on its own thread so the bridge can be polled while waiting for input.
+    let (stdin_tx, stdin_rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = [0u8; 256];
+        let mut stdin = std::io::stdin();
+        while let Ok(n) = stdin.read(&mut buf) {
+            // Dropping the sender on the exit key ends the console as if stdin had closed.
+            let (data, exit) = match buf[..n].iter().position(|b| *b == UART_EXIT_KEY) {
+                Some(idx) => (&buf[..idx], true),
+                None => (&buf[..n], false),
+            };
+            if n == 0 || stdin_tx.send(data.to_vec()).is_err() || exit {
+                break;
+            }
+        }
+    });
+
+    let mut stdout = std::io::stdout();
+    let mut pending = Vec::new();
+    let mut rx = Vec::new();
+    let mut eof = false;
+    loop {
+        loop {
+            match stdin_rx.try_recv() {
+                Ok(data) => pending.extend(data),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => { eof = true; break; },
+            }
+        }
+        if eof && pending.is_empty() {
+            break;
+        }
+
+        let sent = uart.transfer(x7, &pending, &mut rx)?;
+        pending.drain(..sent);
+        if !rx.is_empty() {
+            stdout.write_all(&rx)?;
+            stdout.flush()?;
+            if let Some(log) = log.as_mut() {
+                log.write_all(&rx)?;
+                log.flush()?;
+            }
+            rx.clear();
+        } else if sent == 0 {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+    Ok(())
+}
+
+/// Parse a hex value with an optional `0x` prefix and `_` separators into `len` bits.
+fn hex_to_bits(hex: &str, len: usize) -> anyhow::Result<Vec<bool>> {
+    let digits = hex.trim_start_matches("0x").trim_start_matches("0X").replace('_', "");
+    svf::hex_to_bits(&digits, len).map_err(|e| anyhow!("Invalid hex value {hex}: {e}"))
+}
+
+fn print_pin_states(bscan: &BoundaryScan, states: &BTreeMap<&str, bool>, json: bool)
+    -> anyhow::Result<()>
+{
+    if json {
+        println!("{}", json!({ "pins": states }));
+    } else {
+        for (name, state) in states {
+            let pin = bscan.pin(name)?;
+            println!(" {:>6}: {} ({})", name, *state as u8, pin.port);
+        }
+    }
+    Ok(())
+}
+
+fn print_jtag_chain(chain: &JTAGChain, json: bool) {
     let idcodes = chain.idcodes();
+    if json {
+        let taps: Vec<_> = idcodes.iter().zip(chain.irlens()).enumerate()
+            .map(|(index, (idcode, irlen))| json!({
+                "index": index,
+                "idcode": idcode.map(|id| id.0),
+                "ir_length": irlen,
+                "device": idcode.and_then(taps::lookup).map(|tap| tap.name),
+                "revision": idcode.map(|id| id.version()),
+            })).collect();
+        println!("{}", json!({ "taps": taps }));
+        return;
+    }
+    println!("Detected JTAG chain, closest to TDO first:");
     let lines = chain.to_lines();
     for (idcode, line) in idcodes.iter().zip(lines.iter()) {
-        if let Some(Some(x7)) = idcode.map(X7IDCODE::try_from_idcode) {
-            println!(" - {} [{}]", line, x7.name());
+        if let Some(id) = idcode.and_then(DeviceId::try_from_idcode) {
+            println!(" - {} [{}]", line, id);
+        } else if let Some(tap) = idcode.and_then(taps::lookup) {
+            println!(" - {} [{}]", line, tap.name);
         } else {
             println!(" - {}", line);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_is_valid() {
+        super::cli().debug_assert();
+    }
+
+    #[test]
+    fn assign_targets_explicit_first() {
+        let a35t = Some(IDCODE(X7IDCODE::X7A35T as u32));
+        let a50t = Some(IDCODE(X7IDCODE::X7A50T as u32));
+        let idcodes = [a35t, a50t, a35t];
+        let targets = |files: &[&str]| -> Vec<(Option<usize>, String)> {
+            let matches = cli().get_matches_from(["x7dap", "program"].iter().chain(files));
+            program_targets(&matches)
+        };
+        let strict = Compatibility::strict();
+        let a35t_bit = Some(X7IDCODE::X7A35T as u32);
+
+        // The explicit TAP 0 is assigned before the auto-matched A35T bitstream.
+        let assigned = assign_targets(&idcodes, &targets(&["a.bit", "0=b.bit"]), &[a35t_bit, None], &strict);
+        assert_eq!(assigned.unwrap(), [(2, X7IDCODE::X7A35T), (0, X7IDCODE::X7A35T)]);
+
+        // Auto-matched bitstreams take devices in chain order.
+        let assigned = assign_targets(&idcodes, &targets(&["a.bit", "c.bit"]), &[a35t_bit, a35t_bit], &strict);
+        assert_eq!(assigned.unwrap(), [(0, X7IDCODE::X7A35T), (2, X7IDCODE::X7A35T)]);
+
+        // No A35T is left for the auto-matched bitstream.
+        let files = targets(&["a.bit", "0=b.bit", "2=c.bit"]);
+        assert!(assign_targets(&idcodes, &files, &[a35t_bit, None, None], &strict).is_err());
+        assert!(assign_targets(&idcodes, &targets(&["0=b.bit", "0=c.bit"]), &[None, None], &strict).is_err());
+        assert!(assign_targets(&idcodes, &targets(&["1=b.bit"]), &[a35t_bit], &strict).is_ok());
+        assert!(assign_targets(&idcodes, &targets(&["3=b.bit"]), &[None], &strict).is_err());
+        assert!(assign_targets(&idcodes, &targets(&["b.bit"]), &[None], &strict).is_err());
+    }
+
+    #[test]
+    fn probe_serials() {
+        let probe = |sn: Option<&str>| ProbeInfo {
+            name: None, vid: 0x1209, pid: 0xda42, sn: sn.map(str::to_string), v1_only: false,
+        };
+        assert!(check_probe_serials(&[probe(Some("A")), probe(Some("B"))]).is_ok());
+        assert!(check_probe_serials(&[probe(Some("A")), probe(None)]).is_err());
+        assert!(check_probe_serials(&[probe(Some("A")), probe(Some("B")), probe(Some("A"))]).is_err());
+    }
+
+    #[test]
+    fn program_targets_paths() {
+        let matches = cli().get_matches_from(["x7dap", "program", "1=a.bit", "./1=b.bit", "c=d.bit"]);
+        assert_eq!(program_targets(&matches), [
+            (Some(1), "a.bit".to_string()),
+            (None, "./1=b.bit".to_string()),
+            (None, "c=d.bit".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn record_only_targets() {
+        let check = |args: &[&str]| {
+            let matches = cli().get_matches_from(["x7dap", "--record-only", "--record", "out.svf", "--device", "XC7A35T"].iter().chain(args));
+            check_record_only_targets(&matches).map_err(|e| e.to_string())
+        };
+        assert!(check(&["program", "a.bit"]).is_ok());
+        assert!(check(&["program", "./0=a.bit"]).is_ok());
+        assert!(check(&["shutdown"]).is_ok());
+        assert!(check(&["program", "0=a.bit"]).unwrap_err().contains("TAP index 0"));
+        assert!(check(&["program", "a.bit", "b.bit"]).unwrap_err().contains("Only one bitstream"));
+    }
+}
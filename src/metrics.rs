@@ -0,0 +1,155 @@
+// Copyright 2025 Adam Greig
+// Licensed under the Apache-2.0 and MIT licenses.
+
+//! OpenMetrics exporter for XADC telemetry.
+//!
+//! The exporter serves a single plain-HTTP endpoint which reads the XADC,
+//! status register, and DNA from the device on every scrape.
+
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use crate::{Result, Status, X7, X7IDCODE, XadcReading, MinMaxNow, tap::Tap};
+
+/// Render a set of readings in the OpenMetrics text format.
+pub fn render(idcode: X7IDCODE, dna: &[u8], xadc: &XadcReading, status: &Status) -> String {
+    let mut out = String::new();
+    let dna: String = dna.iter().map(|b| format!("{:02X}", b)).collect();
+
+    out.push_str("# TYPE x7dap_device info\n");
+    out.push_str("# HELP x7dap_device Device identity.\n");
+    writeln!(out, "x7dap_device_info{{device=\"{}\",dna=\"{}\"}} 1", idcode.name(), dna).unwrap();
+
+    out.push_str("# TYPE x7dap_die_temperature_celsius gauge\n");
+    out.push_str("# UNIT x7dap_die_temperature_celsius celsius\n");
+    out.push_str("# HELP x7dap_die_temperature_celsius XADC die temperature.\n");
+    write_min_max_now(&mut out, "x7dap_die_temperature_celsius", "", &xadc.temperature());
+
+    let rails = [
+        ("vccint", Some(xadc.vccint())),
+        ("vccaux", Some(xadc.vccaux())),
+        ("vccbram", Some(xadc.vccbram())),
+        ("vccpint", xadc.vccpint()),
+        ("vccpaux", xadc.vccpaux()),
+        ("vccoddr", xadc.vccoddr()),
+    ];
+    out.push_str("# TYPE x7dap_supply_volts gauge\n");
+    out.push_str("# UNIT x7dap_supply_volts volts\n");
+    out.push_str("# HELP x7dap_supply_volts XADC supply rail voltage.\n");
+    for (rail, reading) in rails {
+        if let Some(reading) = reading {
+            let label = format!("rail=\"{rail}\",");
+            write_min_max_now(&mut out, "x7dap_supply_volts", &label, &reading);
+        }
+    }
+
+    out.push_str("# TYPE x7dap_reference_volts gauge\n");
+    out.push_str("# UNIT x7dap_reference_volts volts\n");
+    out.push_str("# HELP x7dap_reference_volts XADC reference voltage.\n");
+    writeln!(out, "x7dap_reference_volts{{ref=\"vrefp\"}} {}", xadc.vrefp()).unwrap();
+    writeln!(out, "x7dap_reference_volts{{ref=\"vrefn\"}} {}", xadc.vrefn()).unwrap();
+
+    out.push_str("# TYPE x7dap_status_word gauge\n");
+    out.push_str("# HELP x7dap_status_word Raw configuration status register.\n");
+    writeln!(out, "x7dap_status_word {}", status.word()).unwrap();
+
+    let bits = [
+        ("xadc_overtemp", status.xadc_overtemp()),
+        ("dec_error", status.dec_error()),
+        ("id_error", status.id_error()),
+        ("done", status.done()),
+        ("release_done", status.release_done()),
+        ("init_b", status.init_b()),
+        ("init_complete", status.init_complete()),
+        ("ghigh_b", status.ghigh_b()),
+        ("gwe", status.gwe()),
+        ("gts_cfg_b", status.gts_cfg_b()),
+        ("eos", status.eos()),
+        ("dci_match", status.dci_match()),
+        ("mmcm_lock", status.mmcm_lock()),
+        ("part_secured", status.part_secured()),
+        ("crc_error", status.crc_error()),
+    ];
+    out.push_str("# TYPE x7dap_status_bit gauge\n");
+    out.push_str("# HELP x7dap_status_bit Decoded configuration status register bits.\n");
+    for (name, value) in bits {
+        writeln!(out, "x7dap_status_bit{{bit=\"{name}\"}} {}", value as u8).unwrap();
+    }
+
+    out.push_str("# EOF\n");
+    out
+}
+
+fn write_min_max_now(out: &mut String, name: &str, labels: &str, reading: &MinMaxNow) {
+    writeln!(out, "{name}{{{labels}stat=\"current\"}} {}", reading.current()).unwrap();
+    writeln!(out, "{name}{{{labels}stat=\"min\"}} {}", reading.min()).unwrap();
+    writeln!(out, "{name}{{{labels}stat=\"max\"}} {}", reading.max()).unwrap();
+}
+
+/// Time allowed for a client to send its request or receive the response.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serve OpenMetrics for `x7` on `listener` until an I/O error occurs.
+///
+/// Each request to `/metrics` reads fresh values from the device.
+/// Requests are handled one at a time as they all share the probe, so
+/// clients which stall for longer than a few seconds are disconnected.
+pub fn serve<T: Tap>(x7: &mut X7<T>, listener: TcpListener) -> Result<()> {
+    log::info!("Serving metrics on http://{}/metrics", listener.local_addr()?);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        if let Err(e) = handle(x7, stream) {
+            log::warn!("Error handling metrics request: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn handle<T: Tap>(x7: &mut X7<T>, mut stream: TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    log::debug!("Metrics request: {}", request.trim_end());
+
+    // Discard the remaining request headers.
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+    const OPENMETRICS: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+    const PLAIN: &str = "text/plain; charset=utf-8";
+    let (code, content_type, body) = if path == "/metrics" || path == "/" {
+        match read_metrics(x7) {
+            Ok(body) => ("200 OK", OPENMETRICS, body),
+            Err(e) => {
+                log::error!("Error reading device: {}", e);
+                ("500 Internal Server Error", PLAIN, format!("{e}\n"))
+            },
+        }
+    } else {
+        ("404 Not Found", PLAIN, "Not found\n".to_string())
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {code}\r\n\
+         Content-Type: {content_type}\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len(),
+    )?;
+    Ok(())
+}
+
+fn read_metrics<T: Tap>(x7: &mut X7<T>) -> Result<String> {
+    let dna = x7.dna()?;
+    let xadc = x7.xadc()?;
+    let status = x7.status()?;
+    Ok(render(x7.idcode(), &dna, &xadc, &status))
+}
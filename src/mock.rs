@@ -0,0 +1,132 @@
+// Copyright 2025 Adam Greig
+// Licensed under the Apache-2.0 and MIT licenses.
+
+//! Scripted TAP for testing without a probe.
+//!
+//! `MockTap` records every operation performed on it, and returns canned
+//! data for each DR read in the order it was queued:
+//!
+//! ```no_run
+//! use x7dap::{X7, X7IDCODE, mock::MockTap};
+//!
+//! let mut tap = MockTap::new();
+//! tap.push_dr_u32(0x0000_5000);
+//! let mut x7 = X7::new(tap, X7IDCODE::X7A35T);
+//! let status = x7.status().unwrap();
+//! println!("{status:?}, {:?}", x7.tap().ops());
+//! ```
+
+use std::{collections::VecDeque, time::Duration};
+
+use jtagdap::bitvec::append_u32;
+
+use crate::{Error, Result, tap::Tap};
+
+/// A single operation performed on a `MockTap`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Op {
+    TestLogicReset,
+    RunTestIdle(usize),
+    WriteIr(Vec<bool>),
+    WriteDr(Vec<bool>),
+    ReadDr(usize),
+    ExchangeDr(Vec<bool>),
+    Sleep(Duration),
+}
+
+/// TAP which records operations and returns canned DR data.
+#[derive(Clone, Debug, Default)]
+pub struct MockTap {
+    ops: Vec<Op>,
+    responses: VecDeque<Vec<bool>>,
+}
+
+impl MockTap {
+    /// Create a new mock with no canned data.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue `bits` to be returned by the next DR read or exchange.
+    ///
+    /// Reads with no queued data return all zeros.
+    pub fn push_dr(&mut self, bits: Vec<bool>) {
+        self.responses.push_back(bits);
+    }
+
+    /// Queue a 32-bit word, least significant bit first, to be returned by the next DR read.
+    pub fn push_dr_u32(&mut self, word: u32) {
+        let mut bits = Vec::new();
+        append_u32(&mut bits, word);
+        self.push_dr(bits);
+    }
+
+    /// All operations performed so far, in order.
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    /// All IR values written so far, in order.
+    pub fn ir_writes(&self) -> impl Iterator<Item = &[bool]> {
+        self.ops.iter().filter_map(|op| match op {
+            Op::WriteIr(ir) => Some(ir.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// Number of queued responses not yet read.
+    pub fn pending_responses(&self) -> usize {
+        self.responses.len()
+    }
+
+    /// Clear all recorded operations.
+    pub fn clear(&mut self) {
+        self.ops.clear();
+    }
+
+    fn response(&mut self, n: usize) -> Result<Vec<bool>> {
+        match self.responses.pop_front() {
+            Some(bits) if bits.len() == n => Ok(bits),
+            Some(bits) => Err(Error::Other(anyhow::anyhow!(
+                "Mock DR response has {} bits, but {} bits were read", bits.len(), n))),
+            None => Ok(vec![false; n]),
+        }
+    }
+}
+
+impl Tap for MockTap {
+    fn test_logic_reset(&mut self) -> Result<()> {
+        self.ops.push(Op::TestLogicReset);
+        Ok(())
+    }
+
+    fn run_test_idle(&mut self, n: usize) -> Result<()> {
+        self.ops.push(Op::RunTestIdle(n));
+        Ok(())
+    }
+
+    fn write_ir(&mut self, ir: &[bool]) -> Result<()> {
+        self.ops.push(Op::WriteIr(ir.to_vec()));
+        Ok(())
+    }
+
+    fn write_dr(&mut self, dr: &[bool]) -> Result<()> {
+        self.ops.push(Op::WriteDr(dr.to_vec()));
+        Ok(())
+    }
+
+    fn read_dr(&mut self, n: usize) -> Result<Vec<bool>> {
+        self.ops.push(Op::ReadDr(n));
+        self.response(n)
+    }
+
+    fn exchange_dr(&mut self, dr: &[bool]) -> Result<Vec<bool>> {
+        self.ops.push(Op::ExchangeDr(dr.to_vec()));
+        self.response(dr.len())
+    }
+
+    fn sleep(&mut self, duration: Duration) -> Result<()> {
+        self.ops.push(Op::Sleep(duration));
+        Ok(())
+    }
+}
@@ -0,0 +1,629 @@
+// Copyright 2025 Adam Greig
+// Licensed under the Apache-2.0 and MIT licenses.
+
+//! Behavioural model of the 7-series configuration logic.
+//!
+//! `SimX7` implements `Tap` for a single simulated device, so complete
+//! device flows such as programming and status reads can be run without
+//! hardware:
+//!
+//! ```no_run
+//! use x7dap::{X7, X7IDCODE, sim::SimX7};
+//!
+//! let bitstream = std::fs::read("top.bit").unwrap();
+//! let mut x7 = X7::new(SimX7::new(X7IDCODE::X7A35T), X7IDCODE::X7A35T);
+//! x7.program(&bitstream).unwrap();
+//! assert!(x7.tap().is_configured());
+//! ```
+//!
+//! Packets shifted in through CFG_IN are decoded as described in UG470.
+//! Register writes are recorded, writes to IDCODE and CRC are checked with
+//! any error halting configuration until the next JPROGRAM, and register
+//! reads are returned through CFG_OUT. Frame data written to FDRI
+//! is stored by frame address, with FAR treated as a linear frame index and
+//! the frame size given by `Family::frame_words`, and FDRO readback is
+//! preceded by one frame of padding as on the device.
+//! A START command followed by JSTART completes the startup sequence, and
+//! IPROG records the WBSTAR address it would have booted from.
+//!
+//! Timing is not modelled, and encrypted bitstreams are not supported.
+//! Spartan-6 and Virtex-6 devices, with their own instructions, are not modelled.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use crate::{Command, Result, Status, X7IDCODE, tap::Tap};
+
+/// Synchronisation word which starts packet processing.
+const SYNC_WORD: u32 = 0xAA99_5566;
+
+/// CRC-32C polynomial used by the configuration CRC.
+const CRC_POLY: u32 = 0x82F6_3B78;
+
+/// Configuration register addresses.
+mod reg {
+    pub const CRC: u8 = 0x00;
+    pub const FAR: u8 = 0x01;
+    pub const FDRI: u8 = 0x02;
+    pub const FDRO: u8 = 0x03;
+    pub const CMD: u8 = 0x04;
+    pub const STAT: u8 = 0x07;
+    pub const IDCODE: u8 = 0x0C;
+    pub const WBSTAR: u8 = 0x10;
+    pub const BOOTSTS: u8 = 0x16;
+}
+
+/// CMD register commands.
+mod cmd {
+    pub const START: u32 = 0x05;
+    pub const RCRC: u32 = 0x07;
+    pub const SHUTDOWN: u32 = 0x0B;
+    pub const DESYNC: u32 = 0x0D;
+    pub const IPROG: u32 = 0x0F;
+}
+
+/// Packet data currently being received.
+#[derive(Copy, Clone, Debug)]
+struct Packet {
+    reg: u8,
+    remaining: u32,
+}
+
+/// Simulated 7-series device on its own JTAG chain.
+#[derive(Clone, Debug)]
+pub struct SimX7 {
+    idcode: X7IDCODE,
+    dna: u64,
+    ir: u8,
+    xadc: [u16; 128],
+    xadc_result: u32,
+
+    /// Configuration word currently being shifted in.
+    word: u32,
+    word_bits: usize,
+    synced: bool,
+    halted: bool,
+    packet: Option<Packet>,
+    last_reg: u8,
+    regs: [u32; 32],
+    crc: u32,
+    frames: BTreeMap<u32, Vec<u32>>,
+    frame: Vec<u32>,
+    out: VecDeque<bool>,
+
+    crc_error: bool,
+    id_error: bool,
+    start_pending: bool,
+    configured: bool,
+    started: bool,
+    warm_boots: Vec<u32>,
+}
+
+impl SimX7 {
+    /// Create a new unconfigured device.
+    pub fn new(idcode: X7IDCODE) -> Self {
+        SimX7 {
+            idcode,
+            dna: 0,
+            ir: Command::IDCODE as u8,
+            xadc: [0; 128],
+            xadc_result: 0,
+            word: 0,
+            word_bits: 0,
+            synced: false,
+            halted: false,
+            packet: None,
+            last_reg: 0,
+            regs: [0; 32],
+            crc: 0,
+            frames: BTreeMap::new(),
+            frame: Vec::new(),
+            out: VecDeque::new(),
+            crc_error: false,
+            id_error: false,
+            start_pending: false,
+            configured: false,
+            started: false,
+            warm_boots: Vec::new(),
+        }
+    }
+
+    /// Set the device DNA returned by FUSE_DNA.
+    ///
+    /// Only the low `Family::dna_bits` bits are read by the device; UltraScale
+    /// DNA is wider than 64 bits, so its upper bits always read as zero.
+    pub fn with_dna(mut self, dna: u64) -> Self {
+        self.dna = dna;
+        self
+    }
+
+    /// Set the value returned when XADC register `addr` is read.
+    pub fn set_xadc(&mut self, addr: u8, value: u16) {
+        self.xadc[addr as usize & 0x7F] = value;
+    }
+
+    /// Current value of XADC register `addr`.
+    pub fn xadc(&self, addr: u8) -> u16 {
+        self.xadc[addr as usize & 0x7F]
+    }
+
+    /// Current configuration status register.
+    pub fn status(&self) -> Status {
+        let running = self.started;
+        let word = (self.crc_error as u32)
+            | 1 << 2                        // MMCM_LOCK
+            | 1 << 3                        // DCI_MATCH
+            | (running as u32) << 4         // EOS
+            | (running as u32) << 5         // GTS_CFG_B
+            | (running as u32) << 6         // GWE
+            | (self.configured as u32) << 7 // GHIGH_B
+            | 0b101 << 8                    // MODE: JTAG
+            | 1 << 11                       // INIT_COMPLETE
+            | 1 << 12                       // INIT_B
+            | (self.configured as u32) << 13
+            | (self.configured as u32) << 14
+            | (self.id_error as u32) << 15
+            | (if running { 0b100 } else { 0 }) << 18;
+        Status::new(word)
+    }
+
+    /// Number of 32-bit words in one configuration frame of the simulated device.
+    pub fn frame_words(&self) -> usize {
+        self.idcode.family().frame_words()
+    }
+
+    /// Last value written to configuration register `addr`.
+    pub fn register(&self, addr: u8) -> u32 {
+        self.regs[addr as usize & 0x1F]
+    }
+
+    /// Frame data written at frame address `far`, if any.
+    pub fn frame(&self, far: u32) -> Option<&[u32]> {
+        self.frames.get(&far).map(|f| f.as_slice())
+    }
+
+    /// Number of frames written.
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Whether a bitstream has been loaded and started.
+    pub fn is_configured(&self) -> bool {
+        self.configured
+    }
+
+    /// Whether the design is running, that is started and not shut down.
+    pub fn is_running(&self) -> bool {
+        self.started
+    }
+
+    /// WBSTAR value at each IPROG command received, in order.
+    pub fn warm_boots(&self) -> &[u32] {
+        &self.warm_boots
+    }
+
+    /// Current instruction register value.
+    pub fn ir(&self) -> u8 {
+        self.ir
+    }
+
+    /// Clear configuration memory and all state, as JPROGRAM does.
+    fn reset_configuration(&mut self) {
+        self.word = 0;
+        self.word_bits = 0;
+        self.synced = false;
+        self.halted = false;
+        self.packet = None;
+        self.last_reg = 0;
+        self.regs = [0; 32];
+        self.regs[reg::BOOTSTS as usize] = self.bootsts();
+        self.crc = 0;
+        self.frames.clear();
+        self.frame.clear();
+        self.out.clear();
+        self.crc_error = false;
+        self.id_error = false;
+        self.start_pending = false;
+        self.configured = false;
+        self.started = false;
+    }
+
+    fn bootsts(&self) -> u32 {
+        // VALID_0, with IPROG_0 set after a warm boot.
+        1 | (!self.warm_boots.is_empty() as u32) << 2
+    }
+
+    /// Process one bit shifted into CFG_IN, most significant bit of each word first.
+    fn cfg_in_bit(&mut self, bit: bool) {
+        self.word = (self.word << 1) | bit as u32;
+        self.word_bits += 1;
+        if !self.synced {
+            // Search for the sync word at any bit alignment.
+            if self.word == SYNC_WORD {
+                log::trace!("Sim: sync");
+                self.synced = true;
+                self.word = 0;
+                self.word_bits = 0;
+            }
+            return;
+        }
+        if self.word_bits == 32 {
+            let word = self.word;
+            self.word = 0;
+            self.word_bits = 0;
+            self.cfg_in_word(word);
+        }
+    }
+
+    fn cfg_in_word(&mut self, word: u32) {
+        // Packet data.
+        if let Some(packet) = &mut self.packet {
+            packet.remaining -= 1;
+            let reg = packet.reg;
+            if packet.remaining == 0 {
+                self.packet = None;
+            }
+            self.write_register(reg, word);
+            return;
+        }
+
+        // Packet header.
+        let op = (word >> 27) & 0b11;
+        let (reg, count) = match word >> 29 {
+            0b001 => {
+                let reg = ((word >> 13) & 0x1F) as u8;
+                self.last_reg = reg;
+                (reg, word & 0x7FF)
+            },
+            0b010 => (self.last_reg, word & 0x07FF_FFFF),
+            _ => {
+                log::trace!("Sim: ignoring invalid packet header {word:08X}");
+                return;
+            },
+        };
+        match op {
+            0b01 => self.read_register(reg, count),
+            0b10 if count > 0 => self.packet = Some(Packet { reg, remaining: count }),
+            _ => (),
+        }
+    }
+
+    fn write_register(&mut self, reg: u8, value: u32) {
+        log::trace!("Sim: write register {reg:02X} = {value:08X}");
+        if reg == reg::CRC {
+            if value != self.crc {
+                log::debug!("Sim: CRC error, expected {:08X} but got {value:08X}", self.crc);
+                self.crc_error = true;
+                self.halt();
+            }
+            self.crc = 0;
+            return;
+        }
+        self.crc = crc_update(self.crc, reg, value);
+        self.regs[reg as usize & 0x1F] = value;
+        match reg {
+            reg::FDRI => {
+                self.frame.push(value);
+                if self.frame.len() == self.frame_words() {
+                    let far = self.regs[reg::FAR as usize];
+                    self.frames.insert(far, std::mem::take(&mut self.frame));
+                    self.regs[reg::FAR as usize] = far.wrapping_add(1);
+                }
+            },
+            reg::FAR => self.frame.clear(),
+            reg::IDCODE if value & 0x0FFF_FFFF != self.idcode as u32 => {
+                log::debug!("Sim: ID error, bitstream IDCODE {value:08X}");
+                self.id_error = true;
+                self.halt();
+            },
+            reg::CMD => self.command(value & 0x1F),
+            _ => (),
+        }
+    }
+
+    fn command(&mut self, command: u32) {
+        log::trace!("Sim: command {command:02X}");
+        match command {
+            cmd::START => self.start_pending = !self.halted,
+            cmd::RCRC => {
+                self.crc = 0;
+                self.crc_error = false;
+            },
+            cmd::SHUTDOWN => self.started = false,
+            cmd::DESYNC => self.synced = false,
+            cmd::IPROG => {
+                self.warm_boots.push(self.regs[reg::WBSTAR as usize]);
+                self.reset_configuration();
+            },
+            _ => (),
+        }
+    }
+
+    /// Stop configuration after an error, until the next JPROGRAM.
+    ///
+    /// Packet processing resumes after the next sync word, so the
+    /// status register can still be read.
+    fn halt(&mut self) {
+        self.halted = true;
+        self.synced = false;
+        self.packet = None;
+    }
+
+    fn read_register(&mut self, reg: u8, count: u32) {
+        log::trace!("Sim: read register {reg:02X} x {count}");
+        if reg == reg::FDRO {
+            // Readback starts with one frame of padding.
+            let far = self.regs[reg::FAR as usize];
+            let frame_words = self.frame_words();
+            let words = std::iter::repeat_n(0, frame_words)
+                .chain((0..).flat_map(|n| {
+                    self.frames.get(&far.wrapping_add(n)).cloned()
+                        .unwrap_or_else(|| vec![0; frame_words])
+                }))
+                .take(count as usize)
+                .collect::<Vec<_>>();
+            words.into_iter().for_each(|w| self.push_out(w));
+            return;
+        }
+        let value = match reg {
+            reg::STAT => self.status().word(),
+            reg::IDCODE => self.idcode as u32,
+            reg::BOOTSTS => self.bootsts(),
+            reg => self.regs[reg as usize & 0x1F],
+        };
+        for _ in 0..count {
+            self.push_out(value);
+        }
+    }
+
+    /// Queue a word to be read from CFG_OUT, most significant bit first.
+    fn push_out(&mut self, word: u32) {
+        self.out.extend((0..32).rev().map(|i| (word >> i) & 1 == 1));
+    }
+
+    /// Data captured by the current instruction's data register.
+    fn capture(&mut self, n: usize) -> Vec<bool> {
+        let word_bits = |word: u64| (0..n).map(|i| i < 64 && (word >> i) & 1 == 1).collect();
+        match self.ir {
+            x if x == Command::IDCODE as u8 => word_bits(self.idcode as u64),
+            x if x == Command::FUSE_DNA as u8 => word_bits(self.dna),
+            x if x == Command::XADC_DRP as u8 => word_bits(self.xadc_result as u64),
+            x if x == Command::USERCODE as u8 => word_bits(0xFFFF_FFFF),
+            x if x == Command::CFG_OUT as u8 =>
+                (0..n).map(|_| self.out.pop_front().unwrap_or(false)).collect(),
+            _ => vec![false; n],
+        }
+    }
+
+    /// Update the current instruction's data register with `dr`.
+    fn update(&mut self, dr: &[bool]) {
+        match self.ir {
+            x if x == Command::CFG_IN as u8 => dr.iter().for_each(|b| self.cfg_in_bit(*b)),
+            x if x == Command::XADC_DRP as u8 && dr.len() >= 32 => {
+                let word = dr[..32].iter().rev().fold(0u32, |w, b| (w << 1) | *b as u32);
+                let addr = ((word >> 16) & 0x3FF) as usize;
+                match (word >> 26) & 0xF {
+                    0b0001 => {
+                        let value = self.xadc.get(addr).copied().unwrap_or(0);
+                        self.xadc_result = (word & 0xFFFF_0000) | value as u32;
+                    },
+                    0b0010 if addr < self.xadc.len() => self.xadc[addr] = word as u16,
+                    _ => (),
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+impl Tap for SimX7 {
+    fn test_logic_reset(&mut self) -> Result<()> {
+        self.ir = Command::IDCODE as u8;
+        Ok(())
+    }
+
+    fn run_test_idle(&mut self, _n: usize) -> Result<()> {
+        if self.ir == Command::JSTART as u8 && (self.start_pending || self.configured) {
+            self.start_pending = false;
+            self.configured = true;
+            self.started = true;
+        } else if self.ir == Command::JSHUTDOWN as u8 {
+            self.started = false;
+        }
+        Ok(())
+    }
+
+    fn write_ir(&mut self, ir: &[bool]) -> Result<()> {
+        self.ir = ir.iter().take(6).rev().fold(0, |w, b| (w << 1) | *b as u8);
+        if self.ir == Command::JPROGRAM as u8 {
+            log::trace!("Sim: JPROGRAM");
+            self.reset_configuration();
+        }
+        Ok(())
+    }
+
+    fn write_dr(&mut self, dr: &[bool]) -> Result<()> {
+        self.update(dr);
+        Ok(())
+    }
+
+    fn read_dr(&mut self, n: usize) -> Result<Vec<bool>> {
+        let captured = self.capture(n);
+        self.update(&vec![false; n]);
+        Ok(captured)
+    }
+
+    fn exchange_dr(&mut self, dr: &[bool]) -> Result<Vec<bool>> {
+        let captured = self.capture(dr.len());
+        self.update(dr);
+        Ok(captured)
+    }
+
+    fn sleep(&mut self, _duration: Duration) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Update the configuration CRC with a write of `value` to register `reg`.
+///
+/// The CRC is CRC-32C over the 5-bit register address and 32-bit data,
+/// shifted least significant bit first, as computed by the device.
+pub fn crc_update(crc: u32, reg: u8, value: u32) -> u32 {
+    let data = ((reg as u64 & 0x1F) << 32) | value as u64;
+    (0..37).fold(crc, |crc, i| {
+        let bit = ((data >> i) as u32 ^ crc) & 1;
+        (crc >> 1) ^ if bit == 1 { CRC_POLY } else { 0 }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, Family, X7};
+
+    /// Type 1 write packet header for `count` words to `reg`.
+    fn write_header(reg: u8, count: u32) -> u32 {
+        0x3000_0000 | (reg as u32) << 13 | count
+    }
+
+    /// Type 1 read packet header for `count` words from `reg`.
+    fn read_header(reg: u8, count: u32) -> u32 {
+        0x2800_0000 | (reg as u32) << 13 | count
+    }
+
+    /// Shift `words` into CFG_IN, then read `count` words back through CFG_OUT.
+    fn cfg(sim: &mut SimX7, words: &[u32], count: usize) -> Vec<u32> {
+        let bits: Vec<bool> = words.iter().flat_map(|w| (0..32).rev().map(move |i| (w >> i) & 1 == 1)).collect();
+        sim.write_ir(&Command::CFG_IN.bits(Family::Series7).unwrap()).unwrap();
+        sim.write_dr(&bits).unwrap();
+        sim.write_ir(&Command::CFG_OUT.bits(Family::Series7).unwrap()).unwrap();
+        sim.read_dr(count * 32).unwrap()
+            .chunks(32).map(|w| w.iter().fold(0, |w, b| (w << 1) | *b as u32)).collect()
+    }
+
+    /// Synthetic bitstream writing `frames` frames for `idcode`, with the CRC adjusted by `crc_xor`.
+    fn bitstream(idcode: u32, frames: usize, crc_xor: u32) -> Vec<u8> {
+        let frame_words = X7IDCODE::try_from_u32(idcode).unwrap().family().frame_words();
+        let frame_data: Vec<u32> = (0..(frames * frame_words) as u32)
+            .map(|i| i.wrapping_mul(0x9E37_79B9)).collect();
+        let mut words = vec![0xFFFF_FFFF, SYNC_WORD, 0x2000_0000];
+        words.extend([write_header(reg::CMD, 1), cmd::RCRC]);
+        let mut crc = 0;
+        let mut write = |words: &mut Vec<u32>, reg: u8, data: &[u32]| {
+            words.push(write_header(reg, data.len() as u32));
+            for value in data {
+                crc = crc_update(crc, reg, *value);
+                words.push(*value);
+            }
+        };
+        write(&mut words, reg::IDCODE, &[idcode]);
+        write(&mut words, reg::FAR, &[0]);
+        write(&mut words, reg::FDRI, &frame_data);
+        words.extend([write_header(reg::CRC, 1), crc ^ crc_xor]);
+        words.extend([write_header(reg::CMD, 1), cmd::START]);
+        words.extend([write_header(reg::CMD, 1), cmd::DESYNC, 0x2000_0000, 0x2000_0000]);
+        words.iter().flat_map(|w| w.to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn program() {
+        let idcode = X7IDCODE::X7A35T;
+        let mut x7 = X7::new(SimX7::new(idcode), idcode);
+        x7.program(&bitstream(idcode as u32, 3, 0)).unwrap();
+        let sim = x7.tap();
+        assert!(sim.is_configured() && sim.is_running());
+        assert_eq!(sim.frame_count(), 3);
+        assert_eq!(sim.frame(2).unwrap()[0], (2 * sim.frame_words() as u32).wrapping_mul(0x9E37_79B9));
+        assert_eq!(sim.register(reg::IDCODE), idcode as u32);
+
+        // STAT as read back through CFG_OUT.
+        let status = x7.status().unwrap();
+        assert_eq!(status.word(), x7.tap().status().word());
+        assert!(status.done() && status.release_done() && status.init_b() && status.init_complete());
+        assert!(status.gwe() && status.eos());
+        assert!(!status.crc_error() && !status.id_error());
+    }
+
+    #[test]
+    fn program_bad_crc() {
+        let idcode = X7IDCODE::X7A35T;
+        let mut x7 = X7::new(SimX7::new(idcode), idcode);
+        assert!(matches!(x7.program(&bitstream(idcode as u32, 1, 1)), Err(Error::BadStatus)));
+        assert!(!x7.tap().is_configured());
+        let status = x7.status().unwrap();
+        assert!(status.crc_error());
+        assert!(!status.done() && !status.id_error());
+
+        // JPROGRAM clears the error, so a good bitstream can then be loaded.
+        x7.program(&bitstream(idcode as u32, 1, 0)).unwrap();
+        assert!(!x7.status().unwrap().crc_error());
+    }
+
+    #[test]
+    fn program_wrong_idcode() {
+        let idcode = X7IDCODE::X7A35T;
+        let mut x7 = X7::new(SimX7::new(idcode), idcode);
+        assert!(matches!(x7.program(&bitstream(X7IDCODE::X7A100T as u32, 1, 0)), Err(Error::BadStatus)));
+        let sim = x7.tap();
+        assert!(!sim.is_configured());
+        // Configuration halts at the IDCODE write, before any frames.
+        assert_eq!(sim.frame_count(), 0);
+        let status = x7.status().unwrap();
+        assert!(status.id_error());
+        assert!(!status.done() && !status.crc_error());
+    }
+
+    #[test]
+    fn program_ultrascale_frames() {
+        let idcode = X7IDCODE::XKU040;
+        let mut x7 = X7::new(SimX7::new(idcode), idcode);
+        x7.program(&bitstream(idcode as u32, 2, 0)).unwrap();
+        assert_eq!(x7.tap().frame_words(), 123);
+        assert_eq!(x7.tap().frame_count(), 2);
+        assert_eq!(x7.tap().frame(1).unwrap().len(), 123);
+    }
+
+    #[test]
+    fn readback() {
+        let idcode = X7IDCODE::X7A35T;
+        let mut x7 = X7::new(SimX7::new(idcode), idcode);
+        x7.program(&bitstream(idcode as u32, 2, 0)).unwrap();
+        let sim = x7.tap_mut();
+        let n = sim.frame_words();
+        let words = cfg(sim, &[
+            SYNC_WORD, 0x2000_0000,
+            write_header(reg::FAR, 1), 0,
+            read_header(reg::FDRO, 3 * n as u32), 0x2000_0000,
+        ], 3 * n);
+        // One pad frame, then the frames as loaded.
+        assert_eq!(words[..n], vec![0; n]);
+        assert_eq!(&words[n..2 * n], sim.frame(0).unwrap());
+        assert_eq!(&words[2 * n..], sim.frame(1).unwrap());
+    }
+
+    #[test]
+    fn iprog() {
+        let idcode = X7IDCODE::X7A35T;
+        let mut x7 = X7::new(SimX7::new(idcode), idcode);
+        x7.program(&bitstream(idcode as u32, 1, 0)).unwrap();
+        let sim = x7.tap_mut();
+        assert_eq!(cfg(sim, &[SYNC_WORD, read_header(reg::BOOTSTS, 1), 0x2000_0000], 1), [0x01]);
+        cfg(sim, &[
+            SYNC_WORD, 0x2000_0000,
+            write_header(reg::WBSTAR, 1), 0x0040_0000,
+            write_header(reg::CMD, 1), cmd::IPROG, 0x2000_0000,
+        ], 0);
+        assert_eq!(sim.warm_boots(), [0x0040_0000]);
+        assert!(!sim.is_configured() && sim.frame_count() == 0);
+        // IPROG desyncs, and BOOTSTS now shows VALID_0 and IPROG_0.
+        assert_eq!(cfg(sim, &[SYNC_WORD, read_header(reg::BOOTSTS, 1), 0x2000_0000], 1), [0x05]);
+    }
+
+    #[test]
+    fn idcode_ignores_revision() {
+        let idcode = X7IDCODE::X7A35T;
+        let mut x7 = X7::new(SimX7::new(idcode), idcode);
+        x7.program(&bitstream(idcode as u32 | 0x3000_0000, 1, 0)).unwrap();
+        assert!(x7.tap().is_configured());
+    }
+}
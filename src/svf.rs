@@ -0,0 +1,885 @@
+// Copyright 2025 Adam Greig
+// Licensed under the Apache-2.0 and MIT licenses.
+
+//! Serial Vector Format player.
+//!
+//! Plays SVF files against the whole JTAG chain, comparing captured TDO
+//! data against the expected values and reporting any mismatches.
+//!
+//! Supported commands are ENDDR, ENDIR, FREQUENCY, HDR, HIR, TDR, TIR,
+//! SDR, SIR, RUNTEST, STATE, and TRST, which is ignored as there is no
+//! TRST signal. PIO and PIOMAP are not supported.
+//!
+//! `SvfWriter` records operations on a single TAP as SVF, so that they
+//! can be replayed by other programmers.
+
+use std::{collections::VecDeque, fmt, fs, io::Write, path::Path, thread, time::{Duration, Instant}};
+
+use jtagdap::dap::DAP;
+use jtagdap::jtag::{Sequences, Error as JTAGError};
+
+use crate::{Error, Result, tap::Tap};
+
+/// JTAG TAP controller states.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TapState {
+    Reset,
+    Idle,
+    DrSelect,
+    DrCapture,
+    DrShift,
+    DrExit1,
+    DrPause,
+    DrExit2,
+    DrUpdate,
+    IrSelect,
+    IrCapture,
+    IrShift,
+    IrExit1,
+    IrPause,
+    IrExit2,
+    IrUpdate,
+}
+
+impl TapState {
+    const ALL: [TapState; 16] = [
+        TapState::Reset, TapState::Idle,
+        TapState::DrSelect, TapState::DrCapture, TapState::DrShift, TapState::DrExit1,
+        TapState::DrPause, TapState::DrExit2, TapState::DrUpdate,
+        TapState::IrSelect, TapState::IrCapture, TapState::IrShift, TapState::IrExit1,
+        TapState::IrPause, TapState::IrExit2, TapState::IrUpdate,
+    ];
+
+    /// State entered after one TCK cycle with the given TMS value.
+    pub fn next(self, tms: bool) -> TapState {
+        use TapState::*;
+        match (self, tms) {
+            (Reset, false) | (Idle, false) | (DrUpdate, false) | (IrUpdate, false) => Idle,
+            (Reset, true) | (IrSelect, true) => Reset,
+            (Idle, true) | (DrUpdate, true) | (IrUpdate, true) => DrSelect,
+            (DrSelect, false) => DrCapture,
+            (DrSelect, true) => IrSelect,
+            (DrCapture, false) | (DrShift, false) | (DrExit2, false) => DrShift,
+            (DrCapture, true) | (DrShift, true) => DrExit1,
+            (DrExit1, false) | (DrPause, false) => DrPause,
+            (DrExit1, true) | (DrExit2, true) => DrUpdate,
+            (DrPause, true) => DrExit2,
+            (IrSelect, false) => IrCapture,
+            (IrCapture, false) | (IrShift, false) | (IrExit2, false) => IrShift,
+            (IrCapture, true) | (IrShift, true) => IrExit1,
+            (IrExit1, false) | (IrPause, false) => IrPause,
+            (IrExit1, true) | (IrExit2, true) => IrUpdate,
+            (IrPause, true) => IrExit2,
+        }
+    }
+
+    /// Shortest TMS sequence to move from this state to `target`.
+    pub fn path_to(self, target: TapState) -> Vec<bool> {
+        let mut paths: Vec<Option<Vec<bool>>> = vec![None; Self::ALL.len()];
+        paths[self as usize] = Some(Vec::new());
+        let mut queue = VecDeque::from([self]);
+        while let Some(state) = queue.pop_front() {
+            if state == target {
+                break;
+            }
+            let path = paths[state as usize].clone().unwrap();
+            for tms in [false, true] {
+                let next = state.next(tms);
+                if paths[next as usize].is_none() {
+                    let mut next_path = path.clone();
+                    next_path.push(tms);
+                    paths[next as usize] = Some(next_path);
+                    queue.push_back(next);
+                }
+            }
+        }
+        paths[target as usize].take().unwrap()
+    }
+
+    /// Whether the TAP remains in this state while TMS is held.
+    pub fn is_stable(self) -> bool {
+        matches!(self, TapState::Reset | TapState::Idle | TapState::DrPause | TapState::IrPause)
+    }
+
+    /// Parse an SVF state name.
+    pub fn from_svf(name: &str) -> Option<TapState> {
+        Some(match name {
+            "RESET" => TapState::Reset,
+            "IDLE" => TapState::Idle,
+            "DRSELECT" => TapState::DrSelect,
+            "DRCAPTURE" => TapState::DrCapture,
+            "DRSHIFT" => TapState::DrShift,
+            "DREXIT1" => TapState::DrExit1,
+            "DRPAUSE" => TapState::DrPause,
+            "DREXIT2" => TapState::DrExit2,
+            "DRUPDATE" => TapState::DrUpdate,
+            "IRSELECT" => TapState::IrSelect,
+            "IRCAPTURE" => TapState::IrCapture,
+            "IRSHIFT" => TapState::IrShift,
+            "IREXIT1" => TapState::IrExit1,
+            "IRPAUSE" => TapState::IrPause,
+            "IREXIT2" => TapState::IrExit2,
+            "IRUPDATE" => TapState::IrUpdate,
+            _ => return None,
+        })
+    }
+}
+
+/// A TDO comparison failure.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mismatch {
+    /// Line number for SVF files, or command index for XSVF files.
+    pub location: usize,
+    pub expected: String,
+    pub actual: String,
+    pub mask: String,
+}
+
+impl Mismatch {
+    fn new(location: usize, expected: &[bool], actual: &[bool], mask: &[bool]) -> Self {
+        Mismatch {
+            location,
+            expected: bits_to_hex(expected),
+            actual: bits_to_hex(actual),
+            mask: bits_to_hex(mask),
+        }
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: expected TDO {}, got {} (mask {})",
+               self.location, self.expected, self.actual, self.mask)
+    }
+}
+
+/// Plays JTAG operations on the whole chain, tracking the TAP state.
+pub struct Player<'a> {
+    dap: &'a DAP,
+    state: Option<TapState>,
+    freq: Option<f64>,
+    pub(crate) keep_going: bool,
+}
+
+impl<'a> Player<'a> {
+    /// Create a new player using `dap`.
+    pub fn new(dap: &'a DAP) -> Self {
+        Player { dap, state: None, freq: None, keep_going: false }
+    }
+
+    /// Continue after TDO mismatches instead of stopping at the first one.
+    pub fn keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// Play `svf`, returning any TDO mismatches.
+    pub fn play(&mut self, svf: &Svf) -> Result<Vec<Mismatch>> {
+        let mut mismatches = Vec::new();
+        let mut s = SvfState::default();
+        for (line, cmd) in &svf.commands {
+            log::trace!("SVF line {line}: {cmd:?}");
+            match cmd {
+                SvfCommand::EndDr(state) => s.enddr = *state,
+                SvfCommand::EndIr(state) => s.endir = *state,
+                SvfCommand::Frequency(freq) => self.set_frequency(*freq)?,
+                SvfCommand::Hdr(scan) => s.hdr.update(scan),
+                SvfCommand::Hir(scan) => s.hir.update(scan),
+                SvfCommand::Tdr(scan) => s.tdr.update(scan),
+                SvfCommand::Tir(scan) => s.tir.update(scan),
+                SvfCommand::Sdr(scan) | SvfCommand::Sir(scan) => {
+                    let ir = matches!(cmd, SvfCommand::Sir(_));
+                    let (header, data, trailer) = if ir {
+                        s.sir.update(scan);
+                        (&s.hir, &s.sir, &s.tir)
+                    } else {
+                        s.sdr.update(scan);
+                        (&s.hdr, &s.sdr, &s.tdr)
+                    };
+                    let tdi: Vec<bool> = [header, data, trailer].iter()
+                        .flat_map(|p| p.tdi.iter().copied()).collect();
+                    let check = [header, data, trailer].iter().any(|p| p.tdo.is_some());
+                    let end = if ir { s.endir } else { s.enddr };
+                    let tdo = self.scan(ir, &tdi, check, true, end)?;
+                    if check {
+                        let (expected, mask): (Vec<bool>, Vec<bool>) = [header, data, trailer]
+                            .iter().flat_map(|p| p.expected()).unzip();
+                        if let Some(m) = compare(*line, &expected, &tdo, &mask) {
+                            log::warn!("TDO mismatch at line {m}");
+                            mismatches.push(m);
+                            if !self.keep_going {
+                                return Ok(mismatches);
+                            }
+                        }
+                    }
+                },
+                SvfCommand::RunTest { run_state, count, min_time, end_state } => {
+                    if let Some(state) = run_state {
+                        s.run_state = *state;
+                        s.run_end_state = *state;
+                    }
+                    if let Some(state) = end_state {
+                        s.run_end_state = *state;
+                    }
+                    let cycles = match count {
+                        Some((n, true)) => *n,
+                        Some((_, false)) => {
+                            log::warn!("SVF line {line}: SCK clocks are not supported, ignoring");
+                            0
+                        },
+                        None => 0,
+                    };
+                    let min_time = Duration::from_secs_f64(min_time.unwrap_or(0.0));
+                    self.wait(s.run_state, cycles, min_time)?;
+                    self.goto(s.run_end_state)?;
+                },
+                SvfCommand::State(path) => {
+                    for state in path {
+                        self.goto(*state)?;
+                    }
+                },
+                SvfCommand::Trst(mode) => {
+                    if mode != "ABSENT" && mode != "Z" {
+                        log::warn!("SVF line {line}: TRST {mode} ignored, no TRST signal");
+                    }
+                },
+            }
+        }
+        Ok(mismatches)
+    }
+
+    /// Set the TCK frequency, or leave it unchanged if `None`.
+    pub(crate) fn set_frequency(&mut self, freq: Option<f64>) -> Result<()> {
+        if let Some(freq) = freq {
+            log::debug!("Setting TCK to {freq}Hz");
+            self.dap.set_clock(freq as u32).map_err(JTAGError::from)?;
+            self.freq = Some(freq);
+        }
+        Ok(())
+    }
+
+    /// Move to `target` along the shortest path.
+    ///
+    /// Moving to Test-Logic-Reset always clocks TMS high five times.
+    pub(crate) fn goto(&mut self, target: TapState) -> Result<()> {
+        let tms = self.path(target);
+        Sequences::with_dap(self.dap).mode(&tms)?.run()?;
+        self.state = Some(target);
+        Ok(())
+    }
+
+    /// Shift `tdi` through the IR or DR, then move to `end`.
+    ///
+    /// If `exit` is false, the TAP is left in the Shift state and `end` is ignored.
+    /// Returns the captured TDO bits if `capture` is set, or an empty vector.
+    pub(crate) fn scan(&mut self, ir: bool, tdi: &[bool], capture: bool, exit: bool, end: TapState)
+        -> Result<Vec<bool>>
+    {
+        let shift = if ir { TapState::IrShift } else { TapState::DrShift };
+        let mut seqs = Sequences::with_dap(self.dap).mode(&self.path(shift))?;
+        self.state = Some(shift);
+        if !tdi.is_empty() {
+            seqs = match capture {
+                true => seqs.exchange(tdi, exit)?,
+                false => seqs.write(tdi, exit)?,
+            };
+            if exit {
+                self.state = Some(if ir { TapState::IrExit1 } else { TapState::DrExit1 });
+            }
+        }
+        if exit {
+            seqs = seqs.mode(&self.path(end))?;
+            self.state = Some(end);
+        }
+        Ok(seqs.run()?)
+    }
+
+    /// Clock TCK `cycles` times in `state`, waiting at least `min_time` in total.
+    pub(crate) fn wait(&mut self, state: TapState, cycles: u64, min_time: Duration)
+        -> Result<()>
+    {
+        self.goto(state)?;
+        let t0 = Instant::now();
+        let tms = state == TapState::Reset;
+        let mut seqs = Sequences::with_dap(self.dap);
+        seqs.add_sequences(cycles as usize, tms, None, false)?;
+        seqs.run()?;
+        let clock_time = self.freq.map(|f| Duration::from_secs_f64(cycles as f64 / f));
+        let elapsed = t0.elapsed().max(clock_time.unwrap_or_default());
+        if let Some(remaining) = min_time.checked_sub(elapsed) {
+            thread::sleep(remaining);
+        }
+        Ok(())
+    }
+
+    /// TMS sequence to move from the current state to `target`.
+    fn path(&self, target: TapState) -> Vec<bool> {
+        match self.state {
+            Some(state) if target != TapState::Reset => state.path_to(target),
+            _ => {
+                let mut tms = vec![true; 5];
+                tms.extend(TapState::Reset.path_to(target));
+                tms
+            },
+        }
+    }
+}
+
+/// Number of hex digits per line in recorded scan data.
+const HEX_LINE_LEN: usize = 64;
+
+/// Records operations on a single TAP as an SVF file.
+///
+/// Other TAPs on the chain are placed in BYPASS using header and trailer
+/// patterns. Read data is not checked, as it is not known when recording,
+/// and all reads return zeros.
+pub struct SvfWriter {
+    out: Box<dyn Write + Send>,
+    ir_prefix: usize,
+    ir_suffix: usize,
+    dr_prefix: usize,
+    dr_suffix: usize,
+    started: bool,
+}
+
+impl SvfWriter {
+    /// Create a new writer for a chain containing only the recorded TAP.
+    pub fn new(out: Box<dyn Write + Send>) -> Self {
+        SvfWriter { out, ir_prefix: 0, ir_suffix: 0, dr_prefix: 0, dr_suffix: 0, started: false }
+    }
+
+    /// Set the position of the recorded TAP on the chain.
+    ///
+    /// `ir_lengths` lists the IR length of every TAP, closest to TDO first,
+    /// and `index` is the recorded TAP's index in that list.
+    pub fn with_chain(mut self, ir_lengths: &[usize], index: usize) -> Self {
+        self.ir_prefix = ir_lengths[..index].iter().sum();
+        self.ir_suffix = ir_lengths[index + 1..].iter().sum();
+        self.dr_prefix = index;
+        self.dr_suffix = ir_lengths.len() - index - 1;
+        self
+    }
+
+    /// Flush any buffered output.
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+
+    fn scan(&mut self, cmd: &str, tdi: &[bool]) -> Result<()> {
+        let digits = hex_digits(tdi);
+        let mut line = format!("{cmd} {} TDI (", tdi.len());
+        if digits.len() <= HEX_LINE_LEN {
+            line.push_str(&digits);
+        } else {
+            for chunk in digits.as_bytes().chunks(HEX_LINE_LEN) {
+                line.push_str("\n    ");
+                line.push_str(std::str::from_utf8(chunk).unwrap());
+            }
+        }
+        line.push_str(");");
+        self.line(&line)
+    }
+
+    fn line(&mut self, line: &str) -> Result<()> {
+        if !self.started {
+            self.started = true;
+            let pattern = |cmd: &str, n: usize, bit: bool| match n {
+                0 => format!("{cmd} 0;"),
+                n => format!("{cmd} {n} TDI ({});", hex_digits(&vec![bit; n])),
+            };
+            let header = [
+                "TRST ABSENT;".to_string(),
+                "ENDIR IDLE;".to_string(),
+                "ENDDR IDLE;".to_string(),
+                pattern("HIR", self.ir_prefix, true),
+                pattern("TIR", self.ir_suffix, true),
+                pattern("HDR", self.dr_prefix, false),
+                pattern("TDR", self.dr_suffix, false),
+            ].join("\n");
+            self.line(&header)?;
+        }
+        writeln!(self.out, "{line}")?;
+        Ok(())
+    }
+}
+
+impl Tap for SvfWriter {
+    /// Move all TAPs to Test-Logic-Reset.
+    fn test_logic_reset(&mut self) -> Result<()> {
+        self.line("STATE RESET;")
+    }
+
+    /// Clock `n` cycles in Run-Test/Idle.
+    fn run_test_idle(&mut self, n: usize) -> Result<()> {
+        self.line(&format!("RUNTEST IDLE {n} TCK ENDSTATE IDLE;"))
+    }
+
+    /// Wait at least `duration` in Run-Test/Idle.
+    fn sleep(&mut self, duration: Duration) -> Result<()> {
+        self.line(&format!("RUNTEST IDLE {:E} SEC ENDSTATE IDLE;", duration.as_secs_f64()))
+    }
+
+    /// Shift `ir` into the instruction register.
+    fn write_ir(&mut self, ir: &[bool]) -> Result<()> {
+        self.scan("SIR", ir)
+    }
+
+    /// Shift `dr` into the data register.
+    fn write_dr(&mut self, dr: &[bool]) -> Result<()> {
+        self.scan("SDR", dr)
+    }
+
+    /// Shift `n` zero bits into the data register, returning all zeros.
+    fn read_dr(&mut self, n: usize) -> Result<Vec<bool>> {
+        self.scan("SDR", &vec![false; n])?;
+        Ok(vec![false; n])
+    }
+
+    /// Shift `dr` into the data register, returning all zeros.
+    fn exchange_dr(&mut self, dr: &[bool]) -> Result<Vec<bool>> {
+        self.scan("SDR", dr)?;
+        Ok(vec![false; dr.len()])
+    }
+
+    fn is_readback_valid(&self) -> bool {
+        false
+    }
+}
+
+/// Compare `actual` against `expected` where `mask` is set.
+pub(crate) fn compare(location: usize, expected: &[bool], actual: &[bool], mask: &[bool])
+    -> Option<Mismatch>
+{
+    let ok = expected.iter().zip(actual).zip(mask).all(|((e, a), m)| !m || e == a);
+    (!ok).then(|| Mismatch::new(location, expected, actual, mask))
+}
+
+/// Current scan parameters for one of HDR, HIR, TDR, TIR, SDR, or SIR.
+#[derive(Clone, Debug, Default)]
+struct Pattern {
+    tdi: Vec<bool>,
+    tdo: Option<Vec<bool>>,
+    mask: Vec<bool>,
+}
+
+impl Pattern {
+    /// Apply a new scan, keeping TDI and MASK if they are not given and the length is unchanged.
+    fn update(&mut self, scan: &Scan) {
+        let len = scan.len;
+        match &scan.tdi {
+            Some(tdi) => self.tdi = tdi.clone(),
+            None if self.tdi.len() != len => self.tdi = vec![false; len],
+            None => (),
+        }
+        match &scan.mask {
+            Some(mask) => self.mask = mask.clone(),
+            None if self.mask.len() != len => self.mask = vec![true; len],
+            None => (),
+        }
+        self.tdo = scan.tdo.clone();
+    }
+
+    /// Expected TDO and mask bits, with a zero mask when TDO is not checked.
+    fn expected(&self) -> impl Iterator<Item = (bool, bool)> + '_ {
+        let tdo = self.tdo.as_deref();
+        (0..self.tdi.len()).map(move |i| match tdo {
+            Some(tdo) => (tdo[i], self.mask[i]),
+            None => (false, false),
+        })
+    }
+}
+
+/// Player state carried between SVF commands.
+struct SvfState {
+    enddr: TapState,
+    endir: TapState,
+    run_state: TapState,
+    run_end_state: TapState,
+    hdr: Pattern,
+    hir: Pattern,
+    tdr: Pattern,
+    tir: Pattern,
+    sdr: Pattern,
+    sir: Pattern,
+}
+
+impl Default for SvfState {
+    fn default() -> Self {
+        SvfState {
+            enddr: TapState::Idle,
+            endir: TapState::Idle,
+            run_state: TapState::Idle,
+            run_end_state: TapState::Idle,
+            hdr: Pattern::default(),
+            hir: Pattern::default(),
+            tdr: Pattern::default(),
+            tir: Pattern::default(),
+            sdr: Pattern::default(),
+            sir: Pattern::default(),
+        }
+    }
+}
+
+/// Parameters of a single scan command.
+#[derive(Clone, Debug)]
+struct Scan {
+    len: usize,
+    tdi: Option<Vec<bool>>,
+    tdo: Option<Vec<bool>>,
+    mask: Option<Vec<bool>>,
+}
+
+#[derive(Clone, Debug)]
+enum SvfCommand {
+    EndDr(TapState),
+    EndIr(TapState),
+    Frequency(Option<f64>),
+    Hdr(Scan),
+    Hir(Scan),
+    Tdr(Scan),
+    Tir(Scan),
+    Sdr(Scan),
+    Sir(Scan),
+    RunTest {
+        run_state: Option<TapState>,
+        /// Clock count, and whether the clock is TCK rather than SCK.
+        count: Option<(u64, bool)>,
+        min_time: Option<f64>,
+        end_state: Option<TapState>,
+    },
+    State(Vec<TapState>),
+    Trst(String),
+}
+
+/// A parsed SVF file.
+#[derive(Clone, Debug)]
+pub struct Svf {
+    commands: Vec<(usize, SvfCommand)>,
+}
+
+impl Svf {
+    /// Load an SVF file from the provided path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse SVF source.
+    pub fn parse(src: &str) -> Result<Self> {
+        let mut commands = Vec::new();
+        for (line, statement) in statements(src) {
+            let err = |msg: &str| Error::SvfParse(format!("line {line}: {msg}"));
+            let mut words = statement.iter().map(|w| w.as_str());
+            let Some(name) = words.next() else { continue };
+            let words: Vec<&str> = words.collect();
+            let cmd = match name {
+                "ENDDR" | "ENDIR" => {
+                    let state = words.first().and_then(|s| TapState::from_svf(s))
+                        .filter(|s| s.is_stable())
+                        .ok_or_else(|| err("expected stable state"))?;
+                    if name == "ENDDR" { SvfCommand::EndDr(state) } else { SvfCommand::EndIr(state) }
+                },
+                "FREQUENCY" => match words.first() {
+                    Some(freq) => SvfCommand::Frequency(
+                        Some(freq.parse().map_err(|_| err("invalid frequency"))?)),
+                    None => SvfCommand::Frequency(None),
+                },
+                "HDR" => SvfCommand::Hdr(parse_scan(&words).map_err(|e| err(&e))?),
+                "HIR" => SvfCommand::Hir(parse_scan(&words).map_err(|e| err(&e))?),
+                "TDR" => SvfCommand::Tdr(parse_scan(&words).map_err(|e| err(&e))?),
+                "TIR" => SvfCommand::Tir(parse_scan(&words).map_err(|e| err(&e))?),
+                "SDR" => SvfCommand::Sdr(parse_scan(&words).map_err(|e| err(&e))?),
+                "SIR" => SvfCommand::Sir(parse_scan(&words).map_err(|e| err(&e))?),
+                "RUNTEST" => parse_runtest(&words).map_err(|e| err(&e))?,
+                "STATE" => {
+                    let path = words.iter().map(|s| TapState::from_svf(s))
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| err("invalid state"))?;
+                    if !path.last().is_some_and(|s| s.is_stable()) {
+                        return Err(err("STATE must end in a stable state"));
+                    }
+                    SvfCommand::State(path)
+                },
+                "TRST" => SvfCommand::Trst(words.first().unwrap_or(&"").to_string()),
+                "PIO" | "PIOMAP" => return Err(Error::SvfUnsupported(name.to_string())),
+                _ => return Err(err(&format!("unknown command {name}"))),
+            };
+            commands.push((line, cmd));
+        }
+        Ok(Svf { commands })
+    }
+
+    /// Number of commands in the file.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Whether the file contains no commands.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+/// Split SVF source into statements of upper-cased words, each with its starting line number.
+///
+/// Parenthesised hex values are returned as a single word including the
+/// parentheses, with all whitespace removed.
+fn statements(src: &str) -> Vec<(usize, Vec<String>)> {
+    let mut statements = Vec::new();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut start = None;
+    let mut in_parens = false;
+    for (lineno, line) in src.lines().enumerate() {
+        let line = line.split('!').next().unwrap();
+        let line = line.split("//").next().unwrap();
+        for c in line.chars() {
+            if c == '(' {
+                // Join a value to its parameter name, as in `TDI (00)`,
+                // but not to a command name, as in `PIOMAP (IN A)`.
+                if word.is_empty() && words.len() > 1 && let Some(prev) = words.pop() {
+                    word = prev;
+                }
+                in_parens = true;
+            } else if c == ')' {
+                in_parens = false;
+            }
+            if c == ';' && !in_parens {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                if let Some(start) = start.take() {
+                    statements.push((start, std::mem::take(&mut words)));
+                }
+            } else if c.is_whitespace() {
+                if !in_parens && !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            } else {
+                start.get_or_insert(lineno + 1);
+                word.push(c.to_ascii_uppercase());
+            }
+        }
+        if !in_parens && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+    }
+    statements
+}
+
+fn parse_scan(words: &[&str]) -> std::result::Result<Scan, String> {
+    let len: usize = words.first().and_then(|l| l.parse().ok())
+        .ok_or("expected length")?;
+    let mut scan = Scan { len, tdi: None, tdo: None, mask: None };
+    for word in &words[1..] {
+        let (name, value) = word.split_once('(').ok_or("expected hex value in parentheses")?;
+        let value = value.strip_suffix(')').ok_or("expected closing parenthesis")?;
+        let bits = Some(hex_to_bits(value, len)?);
+        match name {
+            "TDI" => scan.tdi = bits,
+            "TDO" => scan.tdo = bits,
+            "MASK" => scan.mask = bits,
+            "SMASK" => (),
+            _ => return Err(format!("unknown parameter {name}")),
+        }
+    }
+    Ok(scan)
+}
+
+fn parse_runtest(words: &[&str]) -> std::result::Result<SvfCommand, String> {
+    let mut words = words.iter().peekable();
+    let mut run_state = None;
+    let mut count = None;
+    let mut min_time = None;
+    let mut end_state = None;
+    if let Some(state) = words.peek().and_then(|w| TapState::from_svf(w)) {
+        run_state = Some(state);
+        words.next();
+    }
+    while let Some(word) = words.next() {
+        if *word == "ENDSTATE" {
+            end_state = Some(words.next().and_then(|s| TapState::from_svf(s))
+                .filter(|s| s.is_stable()).ok_or("expected stable end state")?);
+            continue;
+        }
+        if *word == "MAXIMUM" {
+            words.next();
+            words.next();
+            continue;
+        }
+        let value: f64 = word.parse().map_err(|_| format!("invalid number {word}"))?;
+        match words.next().copied() {
+            Some("TCK") => count = Some((value as u64, true)),
+            Some("SCK") => count = Some((value as u64, false)),
+            Some("SEC") => min_time = Some(value),
+            _ => return Err("expected TCK, SCK, or SEC".to_string()),
+        }
+    }
+    if run_state.is_some_and(|s: TapState| !s.is_stable()) {
+        return Err("run state must be stable".to_string());
+    }
+    Ok(SvfCommand::RunTest { run_state, count, min_time, end_state })
+}
+
+/// Parse a hex value into `len` bits, least significant bit first.
+///
+/// Leading zero digits beyond `len` bits are allowed.
+pub fn hex_to_bits(hex: &str, len: usize) -> std::result::Result<Vec<bool>, String> {
+    let mut bits = Vec::with_capacity(len);
+    for digit in hex.chars().rev() {
+        let nibble = digit.to_digit(16).ok_or_else(|| format!("invalid hex digit {digit}"))?;
+        for i in 0..4 {
+            bits.push((nibble >> i) & 1 == 1);
+        }
+    }
+    if bits.iter().skip(len).any(|b| *b) {
+        return Err(format!("value does not fit in {len} bits"));
+    }
+    bits.resize(len, false);
+    Ok(bits)
+}
+
+/// Format bits, least significant bit first, as a hex value.
+pub fn bits_to_hex(bits: &[bool]) -> String {
+    format!("0x{}", hex_digits(bits))
+}
+
+/// Format bits, least significant bit first, as hex digits without a prefix.
+fn hex_digits(bits: &[bool]) -> String {
+    bits.chunks(4).rev().map(|nibble| {
+        let v = nibble.iter().enumerate().fold(0, |v, (i, b)| v | ((*b as u32) << i));
+        char::from_digit(v, 16).unwrap().to_ascii_uppercase()
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits(v: u64, len: usize) -> Vec<bool> {
+        (0..len).map(|i| (v >> i) & 1 == 1).collect()
+    }
+
+    #[test]
+    fn path_to_shortest() {
+        use TapState::*;
+        assert_eq!(Idle.path_to(Idle), Vec::<bool>::new());
+        assert_eq!(Idle.path_to(DrShift), [true, false, false]);
+        assert_eq!(Idle.path_to(IrShift), [true, true, false, false]);
+        assert_eq!(DrExit1.path_to(Idle), [true, false]);
+        assert_eq!(IrPause.path_to(DrPause), [true, true, true, false, true, false]);
+        assert_eq!(DrShift.path_to(Reset), [true, true, true, true, true]);
+    }
+
+    #[test]
+    fn path_to_reaches_every_state() {
+        for from in TapState::ALL {
+            for to in TapState::ALL {
+                let path = from.path_to(to);
+                assert_eq!(path.iter().fold(from, |s, tms| s.next(*tms)), to, "{from:?} to {to:?}");
+                // Every state is reachable from every other within the TAP's diameter.
+                assert!(path.len() <= 8);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_scans() {
+        let svf = Svf::parse("
+            ! Comment
+            HDR 2 TDI (0);
+            HIR 3 TDI (7);
+            TDR 0;
+            TIR 0;
+            SIR 6 TDI (09);
+            SDR 32 TDI (00000000) TDO (0362D093)
+                MASK (0FFFFFFF);  // IDCODE, ignoring version
+        ").unwrap();
+        assert_eq!(svf.len(), 6);
+        let SvfCommand::Hdr(hdr) = &svf.commands[0].1 else { panic!() };
+        assert_eq!((hdr.len, hdr.tdi.as_deref(), hdr.tdo.as_ref()), (2, Some(&[false, false][..]), None));
+        let SvfCommand::Hir(hir) = &svf.commands[1].1 else { panic!() };
+        assert_eq!(hir.tdi, Some(vec![true; 3]));
+        let SvfCommand::Tdr(tdr) = &svf.commands[2].1 else { panic!() };
+        assert_eq!(tdr.len, 0);
+        assert!(matches!(&svf.commands[3].1, SvfCommand::Tir(tir) if tir.len == 0));
+        let SvfCommand::Sir(sir) = &svf.commands[4].1 else { panic!() };
+        assert_eq!(sir.tdi, Some(bits(0x09, 6)));
+        let (line, SvfCommand::Sdr(sdr)) = &svf.commands[5] else { panic!() };
+        assert_eq!(*line, 8);
+        assert_eq!(sdr.tdi, Some(vec![false; 32]));
+        assert_eq!(sdr.tdo, Some(bits(0x0362D093, 32)));
+        assert_eq!(sdr.mask, Some(bits(0x0FFFFFFF, 32)));
+    }
+
+    #[test]
+    fn parse_other_commands() {
+        let svf = Svf::parse("
+            ENDDR DRPAUSE; endir idle;
+            FREQUENCY 1E6 HZ;
+            STATE RESET IDLE;
+            RUNTEST IDLE 100 TCK 1.0E-3 SEC ENDSTATE DRPAUSE;
+            TRST OFF;
+        ").unwrap();
+        assert!(matches!(svf.commands[0].1, SvfCommand::EndDr(TapState::DrPause)));
+        assert!(matches!(svf.commands[1].1, SvfCommand::EndIr(TapState::Idle)));
+        assert!(matches!(svf.commands[2].1, SvfCommand::Frequency(Some(f)) if f == 1e6));
+        let SvfCommand::State(path) = &svf.commands[3].1 else { panic!() };
+        assert_eq!(path, &[TapState::Reset, TapState::Idle]);
+        let SvfCommand::RunTest { run_state, count, min_time, end_state } = svf.commands[4].1 else { panic!() };
+        assert_eq!(run_state, Some(TapState::Idle));
+        assert_eq!(count, Some((100, true)));
+        assert_eq!(min_time, Some(1e-3));
+        assert_eq!(end_state, Some(TapState::DrPause));
+        assert!(matches!(&svf.commands[5].1, SvfCommand::Trst(mode) if mode == "OFF"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(matches!(Svf::parse("SDR 4 TDI (1F);"), Err(Error::SvfParse(_))));
+        assert!(matches!(Svf::parse("SDR 8 TDI (XY);"), Err(Error::SvfParse(_))));
+        assert!(matches!(Svf::parse("SDR 8 FOO (00);"), Err(Error::SvfParse(_))));
+        assert!(matches!(Svf::parse("ENDDR DRSHIFT;"), Err(Error::SvfParse(_))));
+        assert!(matches!(Svf::parse("STATE IDLE DRSHIFT;"), Err(Error::SvfParse(_))));
+        assert!(matches!(Svf::parse("RUNTEST 10 FOO;"), Err(Error::SvfParse(_))));
+        assert!(matches!(Svf::parse("PIOMAP (IN A);"), Err(Error::SvfUnsupported(_))));
+        let Err(Error::SvfParse(msg)) = Svf::parse("\n\nBOGUS;") else { panic!() };
+        assert!(msg.starts_with("line 3:"), "{msg}");
+    }
+
+    #[test]
+    fn pattern_keeps_tdi_and_mask() {
+        let scan = |len, tdi: Option<u64>, tdo: Option<u64>, mask: Option<u64>| Scan {
+            len,
+            tdi: tdi.map(|v| bits(v, len)),
+            tdo: tdo.map(|v| bits(v, len)),
+            mask: mask.map(|v| bits(v, len)),
+        };
+        let mut pattern = Pattern::default();
+        pattern.update(&scan(4, Some(0x5), Some(0xA), Some(0x3)));
+        assert_eq!(pattern.expected().collect::<Vec<_>>(),
+                   [(false, true), (true, true), (false, false), (true, false)]);
+
+        // Same length: TDI and MASK are kept, TDO is not.
+        pattern.update(&scan(4, None, None, None));
+        assert_eq!(pattern.tdi, bits(0x5, 4));
+        assert_eq!(pattern.mask, bits(0x3, 4));
+        assert!(pattern.expected().all(|(_, mask)| !mask));
+        pattern.update(&scan(4, None, Some(0xF), None));
+        assert_eq!(pattern.expected().filter(|(_, mask)| *mask).count(), 2);
+
+        // New length: TDI defaults to zeros and MASK to all ones.
+        pattern.update(&scan(6, None, Some(0x3F), None));
+        assert_eq!(pattern.tdi, vec![false; 6]);
+        assert_eq!(pattern.mask, vec![true; 6]);
+    }
+
+    #[test]
+    fn compare_masked() {
+        let expected = bits(0b1010, 4);
+        assert!(compare(0, &expected, &bits(0b1110, 4), &bits(0b1011, 4)).is_none());
+        let mismatch = compare(7, &expected, &bits(0b1011, 4), &bits(0b1011, 4)).unwrap();
+        assert_eq!(mismatch.location, 7);
+        assert_eq!((mismatch.expected.as_str(), mismatch.actual.as_str()), ("0xA", "0xB"));
+    }
+}
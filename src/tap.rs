@@ -0,0 +1,88 @@
+// Copyright 2025 Adam Greig
+// Licensed under the Apache-2.0 and MIT licenses.
+
+//! Hardware-independent access to a single JTAG TAP.
+//!
+//! `X7` performs all device operations through the `Tap` trait, which is
+//! implemented for a probe's `JTAGTAP`, for recording as SVF with
+//! `SvfWriter`, and for testing with `MockTap`.
+
+use std::time::Duration;
+
+use jtagdap::jtag::JTAGTAP;
+
+use crate::Result;
+
+/// JTAG operations on a single TAP.
+///
+/// IR and DR shifts contain only this TAP's bits, with any other TAPs on
+/// the chain handled by the implementation.
+pub trait Tap {
+    /// Move to Test-Logic-Reset.
+    fn test_logic_reset(&mut self) -> Result<()>;
+
+    /// Move to Run-Test/Idle and remain there for `n` clock cycles.
+    fn run_test_idle(&mut self, n: usize) -> Result<()>;
+
+    /// Shift `ir` into the instruction register, ending in Update-IR.
+    fn write_ir(&mut self, ir: &[bool]) -> Result<()>;
+
+    /// Shift `dr` into the data register, ending in Exit1-DR.
+    fn write_dr(&mut self, dr: &[bool]) -> Result<()>;
+
+    /// Shift `dr` into the data register, ending in Exit1-DR,
+    /// calling `cb` at intervals with the number of bits written so far.
+    fn write_dr_cb(&mut self, dr: &[bool], cb: &dyn Fn(usize)) -> Result<()> {
+        cb(0);
+        self.write_dr(dr)?;
+        cb(dr.len());
+        Ok(())
+    }
+
+    /// Read `n` bits from the data register, ending in Exit1-DR.
+    fn read_dr(&mut self, n: usize) -> Result<Vec<bool>>;
+
+    /// Shift `dr` into the data register while reading it, ending in Exit1-DR.
+    fn exchange_dr(&mut self, dr: &[bool]) -> Result<Vec<bool>>;
+
+    /// Wait for at least `duration`.
+    fn sleep(&mut self, duration: Duration) -> Result<()> {
+        std::thread::sleep(duration);
+        Ok(())
+    }
+
+    /// Whether data read from this TAP comes from a real device and can be checked.
+    fn is_readback_valid(&self) -> bool {
+        true
+    }
+}
+
+impl Tap for JTAGTAP {
+    fn test_logic_reset(&mut self) -> Result<()> {
+        Ok(JTAGTAP::test_logic_reset(self)?)
+    }
+
+    fn run_test_idle(&mut self, n: usize) -> Result<()> {
+        Ok(JTAGTAP::run_test_idle(self, n)?)
+    }
+
+    fn write_ir(&mut self, ir: &[bool]) -> Result<()> {
+        Ok(JTAGTAP::write_ir(self, ir)?)
+    }
+
+    fn write_dr(&mut self, dr: &[bool]) -> Result<()> {
+        Ok(JTAGTAP::write_dr(self, dr)?)
+    }
+
+    fn write_dr_cb(&mut self, dr: &[bool], cb: &dyn Fn(usize)) -> Result<()> {
+        Ok(JTAGTAP::write_dr_cb(self, dr, cb)?)
+    }
+
+    fn read_dr(&mut self, n: usize) -> Result<Vec<bool>> {
+        Ok(JTAGTAP::read_dr(self, n)?)
+    }
+
+    fn exchange_dr(&mut self, dr: &[bool]) -> Result<Vec<bool>> {
+        Ok(JTAGTAP::exchange_dr(self, dr)?)
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright 2025 Adam Greig
+// Licensed under the Apache-2.0 and MIT licenses.
+
+//! Database of common JTAG TAPs.
+//!
+//! Known TAPs are used to name devices in scan output, and to work out IR
+//! lengths on chains where they cannot be detected from the IR scan alone.
+
+use jtagdap::{bitvec::drain_u32, jtag::{IDCODE, JTAG, Error as JTAGError}};
+
+use crate::{Result, X7IDCODE};
+
+/// Number of DR bits to read when detecting IDCODEs, in addition to one IDCODE.
+const MAX_DR_BITS: usize = 1024;
+
+/// A known JTAG TAP.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct KnownTap {
+    /// IDCODE, with any bits not set in `mask` ignored.
+    pub idcode: u32,
+    pub mask: u32,
+    pub name: &'static str,
+    pub ir_length: usize,
+}
+
+impl KnownTap {
+    const fn new(idcode: u32, name: &'static str, ir_length: usize) -> Self {
+        KnownTap { idcode, mask: 0x0FFF_FFFF, name, ir_length }
+    }
+
+    const fn masked(idcode: u32, mask: u32, name: &'static str, ir_length: usize) -> Self {
+        KnownTap { idcode, mask, name, ir_length }
+    }
+
+    /// Whether `idcode` matches this TAP.
+    pub fn matches(&self, idcode: IDCODE) -> bool {
+        idcode.0 & self.mask == self.idcode & self.mask
+    }
+}
+
+/// Known TAPs other than Xilinx FPGAs, which are found from `X7IDCODE`.
+pub const KNOWN_TAPS: &[KnownTap] = &[
+    // ARM
+    KnownTap::masked(0x0BA0_0477, 0x0FFF_0FFF, "ARM JTAG-DP", 4),
+
+    // STMicroelectronics, where the part number is 0x6 followed by the
+    // 0x4xx DEV_ID, and the version field is the silicon revision.
+    KnownTap::masked(0x0640_0041, 0x0FF0_0FFF, "STM32 boundary scan", 5),
+
+    // Xilinx
+    KnownTap::new(0x0504_4093, "XCF01S", 8),
+    KnownTap::new(0x0504_5093, "XCF02S", 8),
+    KnownTap::new(0x0504_6093, "XCF04S", 8),
+    KnownTap::new(0x0950_2093, "XC9536", 8),
+    KnownTap::new(0x0950_4093, "XC9572", 8),
+    KnownTap::new(0x0950_6093, "XC95108", 8),
+    KnownTap::new(0x0950_8093, "XC95144", 8),
+    KnownTap::new(0x0951_2093, "XC95216", 8),
+    KnownTap::new(0x0951_6093, "XC95288", 8),
+    KnownTap::new(0x0960_2093, "XC9536XL", 8),
+    KnownTap::new(0x0960_4093, "XC9572XL", 8),
+    KnownTap::new(0x0960_8093, "XC95144XL", 8),
+    KnownTap::new(0x0961_6093, "XC95288XL", 8),
+
+    // Xilinx CoolRunner-II, where the part number also encodes the package.
+    KnownTap::masked(0x06C0_0093, 0x0FC0_0FFF, "XC2C", 8),
+
+    // Lattice, where ECP5 variants differ only in the version field.
+    KnownTap::masked(0x2111_1043, 0xFFFF_FFFF, "LFE5U-12", 8),
+    KnownTap::masked(0x4111_1043, 0xFFFF_FFFF, "LFE5U-25", 8),
+    KnownTap::masked(0x4111_2043, 0xFFFF_FFFF, "LFE5U-45", 8),
+    KnownTap::masked(0x4111_3043, 0xFFFF_FFFF, "LFE5U-85", 8),
+    KnownTap::masked(0x0111_1043, 0xFFFF_FFFF, "LFE5UM-25", 8),
+    KnownTap::masked(0x0111_2043, 0xFFFF_FFFF, "LFE5UM-45", 8),
+    KnownTap::masked(0x0111_3043, 0xFFFF_FFFF, "LFE5UM-85", 8),
+    KnownTap::masked(0x8111_1043, 0xFFFF_FFFF, "LFE5UM5G-25", 8),
+    KnownTap::masked(0x8111_2043, 0xFFFF_FFFF, "LFE5UM5G-45", 8),
+    KnownTap::masked(0x8111_3043, 0xFFFF_FFFF, "LFE5UM5G-85", 8),
+
+    // Altera
+    KnownTap::new(0x020A_10DD, "EPM240", 10),
+    KnownTap::new(0x020A_20DD, "EPM570", 10),
+    KnownTap::new(0x020A_30DD, "EPM1270", 10),
+    KnownTap::new(0x020A_40DD, "EPM2210", 10),
+    KnownTap::new(0x020F_10DD, "EP4CE6/EP4CE10", 10),
+    KnownTap::new(0x020F_20DD, "EP4CE15", 10),
+    KnownTap::new(0x020F_30DD, "EP4CE22", 10),
+    KnownTap::new(0x020F_40DD, "EP4CE30/EP4CE40", 10),
+    KnownTap::new(0x020F_50DD, "EP4CE55", 10),
+    KnownTap::new(0x020F_60DD, "EP4CE75", 10),
+    KnownTap::new(0x020F_70DD, "EP4CE115", 10),
+
+    // Microchip (Atmel)
+    KnownTap::new(0x0150_203F, "ATF1502AS", 10),
+    KnownTap::new(0x0150_403F, "ATF1504AS", 10),
+    KnownTap::new(0x0150_803F, "ATF1508AS", 10),
+];
+
+/// Look up a known TAP by IDCODE.
+pub fn lookup(idcode: IDCODE) -> Option<KnownTap> {
+    if let Some(x7) = X7IDCODE::try_from_idcode(idcode) {
+        return Some(KnownTap::new(x7 as u32, x7.name(), x7.ir_length()));
+    }
+    KNOWN_TAPS.iter().find(|tap| tap.matches(idcode)).copied()
+}
+
+/// IR lengths for every TAP in `idcodes`, if they are all known.
+///
+/// Virtex-7 HT devices are treated as unknown, as the IR lengths of their
+/// GTZ TAPs are not known.
+pub fn ir_lengths(idcodes: &[Option<IDCODE>]) -> Option<Vec<usize>> {
+    idcodes.iter().map(|id| {
+        let id = (*id)?;
+        if X7IDCODE::try_from_idcode(id).is_some_and(|x7| x7.has_gtz()) {
+            return None;
+        }
+        lookup(id).map(|tap| tap.ir_length)
+    }).collect()
+}
+
+/// Read the IDCODE of every TAP in the chain, without scanning IR.
+///
+/// TAPs without an IDCODE are represented by None.
+pub fn read_idcodes(jtag: &mut JTAG) -> Result<Vec<Option<IDCODE>>> {
+    jtag.enter_test_logic_reset()?;
+    let data = jtag.read_dr(MAX_DR_BITS + 32)?;
+    jtag.enter_test_logic_reset()?;
+
+    // The chain is followed by the 1s shifted in, which read as an IDCODE of all 1s.
+    let mut dr = &data[..];
+    let mut idcodes = Vec::new();
+    while dr.len() >= 32 {
+        if dr[0] {
+            let (word, rest) = drain_u32(dr)?;
+            if word == 0xFFFF_FFFF {
+                return Ok(idcodes);
+            }
+            let idcode = IDCODE(word);
+            if !idcode.valid() {
+                log::error!("Invalid IDCODE: {:08X}", word);
+                return Err(JTAGError::InvalidIDCODE.into());
+            }
+            idcodes.push(Some(idcode));
+            dr = rest;
+        } else {
+            idcodes.push(None);
+            dr = &dr[1..];
+        }
+    }
+    log::error!("JTAG DR scan chain either broken or too long");
+    Err(JTAGError::ScanChainBroken.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_known() {
+        assert_eq!(lookup(IDCODE(0x1362_D093)).unwrap().name, "X7A35T");
+        assert_eq!(lookup(IDCODE(0x4BA0_0477)).unwrap().ir_length, 4);
+        assert_eq!(lookup(IDCODE(0x0641_3041)).unwrap().name, "STM32 boundary scan");
+        assert_eq!(lookup(IDCODE(0x2645_0041)).unwrap().name, "STM32 boundary scan");
+        assert_eq!(lookup(IDCODE(0x4111_3043)).unwrap().name, "LFE5U-85");
+    }
+
+    #[test]
+    fn lookup_unknown() {
+        // Other STMicroelectronics parts share the manufacturer code.
+        assert_eq!(lookup(IDCODE(0x0123_4041)), None);
+        assert_eq!(lookup(IDCODE(0x0000_0041)), None);
+        // ECP5 variants must match exactly.
+        assert_eq!(lookup(IDCODE(0x3111_3043)), None);
+    }
+
+    #[test]
+    fn chain_ir_lengths() {
+        let chain = [Some(IDCODE(0x0372_7093)), Some(IDCODE(0x4BA0_0477))];
+        assert!(X7IDCODE::is_zynq7000_chain(&chain));
+        assert_eq!(ir_lengths(&chain).unwrap(), crate::ZYNQ7000_IR_LENGTHS);
+        assert_eq!(ir_lengths(&[Some(IDCODE(0x0641_3041)), Some(IDCODE(0x0362_D093))]).unwrap(), [5, 6]);
+        // CPLDs ahead of an FPGA.
+        let chain = [Some(IDCODE(0x06E5_E093)), Some(IDCODE(0x5960_4093)), Some(IDCODE(0x0362_D093))];
+        assert_eq!(ir_lengths(&chain).unwrap(), [8, 8, 6]);
+        assert_eq!(ir_lengths(&[Some(IDCODE(0x0123_4041))]), None);
+        assert_eq!(ir_lengths(&[None]), None);
+    }
+}
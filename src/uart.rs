@@ -0,0 +1,153 @@
+// Copyright 2025 Adam Greig
+// Licensed under the Apache-2.0 and MIT licenses.
+
+//! Byte stream access to a JTAG UART bridge in the design.
+//!
+//! The bridge sits on a BSCANE2 USER data register, USER3 by default,
+//! which is 10 bits long, shifted least significant bit first.
+//!
+//! Bits shifted in:
+//!
+//! | Bits    | Field                                          |
+//! |---------|------------------------------------------------|
+//! | 7:0     | Byte to send to the design                     |
+//! | 8       | Valid: the byte should be sent                 |
+//! | 9       | Zero                                           |
+//!
+//! Bits captured, at Capture-DR:
+//!
+//! | Bits    | Field                                          |
+//! |---------|------------------------------------------------|
+//! | 7:0     | Byte received from the design                  |
+//! | 8       | Valid: a byte was received and is now consumed |
+//! | 9       | Ready: the design can accept a byte            |
+//!
+//! A byte shifted in is only accepted by the design if ready was set in
+//! the same shift, otherwise it must be sent again.
+//!
+//! A Verilog implementation of the bridge for 7-series devices is provided
+//! in `hdl/x7dap_jtag_uart.v` in the x7dap repository.
+
+use jtagdap::bitvec::{append_u8, drain_bit, drain_u8};
+
+use crate::{Result, X7, tap::Tap};
+
+/// Length of the bridge data register.
+const DR_LEN: usize = 10;
+
+/// Maximum number of shifts to spend receiving bytes in one `transfer`.
+const MAX_RX_SHIFTS: usize = 64;
+
+/// JTAG UART bridge on a user data register.
+#[derive(Copy, Clone, Debug)]
+pub struct JtagUart {
+    user: u8,
+}
+
+impl Default for JtagUart {
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
+impl JtagUart {
+    /// Create a new bridge accessed through register USER`user`.
+    pub fn new(user: u8) -> Self {
+        JtagUart { user }
+    }
+
+    /// Perform a single shift, optionally sending `tx`.
+    ///
+    /// Returns the byte received, if any, and whether `tx` was accepted.
+    pub fn exchange<T: Tap>(&self, x7: &mut X7<T>, tx: Option<u8>) -> Result<(Option<u8>, bool)> {
+        let mut bits = Vec::with_capacity(DR_LEN);
+        append_u8(&mut bits, tx.unwrap_or(0));
+        bits.push(tx.is_some());
+        let captured = x7.user_dr(self.user, &bits, DR_LEN)?;
+        let (rx, rest) = drain_u8(&captured)?;
+        let (valid, rest) = drain_bit(rest)?;
+        let (ready, _) = drain_bit(rest)?;
+        Ok(((valid == 1).then_some(rx), tx.is_some() && ready == 1))
+    }
+
+    /// Send as much of `tx` as the design will accept, appending any received bytes to `rx`.
+    ///
+    /// Stops sending at the first byte the design is not ready for, and keeps
+    /// receiving while bytes are available, up to a limit. Returns the number
+    /// of bytes of `tx` that were sent.
+    pub fn transfer<T: Tap>(&self, x7: &mut X7<T>, tx: &[u8], rx: &mut Vec<u8>) -> Result<usize> {
+        let mut sent = 0;
+        let mut rx_shifts = 0;
+        loop {
+            let (byte, accepted) = self.exchange(x7, tx.get(sent).copied())?;
+            if let Some(byte) = byte {
+                rx.push(byte);
+            }
+            if accepted {
+                sent += 1;
+                continue;
+            }
+            rx_shifts += 1;
+            if byte.is_none() || rx_shifts >= MAX_RX_SHIFTS {
+                break;
+            }
+        }
+        Ok(sent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jtagdap::bitvec::append_u8;
+    use crate::{X7IDCODE, mock::{MockTap, Op}};
+
+    /// Bridge register contents captured with `rx` and `ready`.
+    fn captured(rx: Option<u8>, ready: bool) -> Vec<bool> {
+        let mut bits = Vec::new();
+        append_u8(&mut bits, rx.unwrap_or(0));
+        bits.extend([rx.is_some(), ready]);
+        bits
+    }
+
+    /// Bits shifted in to send `tx`.
+    fn sent(tx: Option<u8>) -> Vec<bool> {
+        let mut bits = Vec::new();
+        append_u8(&mut bits, tx.unwrap_or(0));
+        bits.extend([tx.is_some(), false]);
+        bits
+    }
+
+    #[test]
+    fn transfer() {
+        let mut tap = MockTap::new();
+        // Not ready for the first byte, but one is received.
+        tap.push_dr(captured(Some(b'x'), false));
+        tap.push_dr(captured(None, true));
+        tap.push_dr(captured(Some(b'y'), true));
+        // Not ready for the third byte, and none received.
+        tap.push_dr(captured(None, false));
+        let mut x7 = X7::new(tap, X7IDCODE::X7A35T);
+        let mut rx = Vec::new();
+        assert_eq!(JtagUart::default().transfer(&mut x7, b"abc", &mut rx).unwrap(), 2);
+        assert_eq!(rx, b"xy");
+        let shifted: Vec<&Vec<bool>> = x7.tap().ops().iter().filter_map(|op| match op {
+            Op::ExchangeDr(dr) => Some(dr),
+            _ => None,
+        }).collect();
+        // The first byte is sent again after it was not accepted.
+        assert_eq!(shifted, [&sent(Some(b'a')), &sent(Some(b'a')), &sent(Some(b'b')), &sent(Some(b'c'))]);
+    }
+
+    #[test]
+    fn receive_only() {
+        let mut tap = MockTap::new();
+        tap.push_dr(captured(Some(b'x'), true));
+        tap.push_dr(captured(None, true));
+        let mut x7 = X7::new(tap, X7IDCODE::X7A35T);
+        let mut rx = Vec::new();
+        assert_eq!(JtagUart::default().transfer(&mut x7, &[], &mut rx).unwrap(), 0);
+        assert_eq!(rx, b"x");
+        assert_eq!(x7.tap().pending_responses(), 0);
+    }
+}
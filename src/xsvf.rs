@@ -0,0 +1,318 @@
+// Copyright 2025 Adam Greig
+// Licensed under the Apache-2.0 and MIT licenses.
+
+//! Xilinx compact Serial Vector Format player.
+//!
+//! XSVF is the binary SVF encoding described in XAPP503, and is played
+//! using the same `Player` as SVF files. Mismatch locations are reported
+//! as the index of the failing command.
+//!
+//! XSETSDRMASKS and XSDRINC are not supported.
+
+use std::{fs, path::Path, time::Duration};
+
+use jtagdap::bitvec::bytes_to_bits;
+
+use crate::{Error, Result, svf::{Mismatch, Player, TapState, compare}};
+
+#[derive(Clone, Debug)]
+enum XsvfCommand {
+    Complete,
+    TdoMask(Vec<bool>),
+    Sir(Vec<bool>),
+    Sdr(Vec<bool>),
+    RunTest(u32),
+    Repeat(u8),
+    SdrSize(usize),
+    SdrTdo(Vec<bool>, Vec<bool>),
+    /// Shift part of DR, with TDO to check if any, and whether this is the first and last part.
+    SdrPart { tdi: Vec<bool>, tdo: Option<Vec<bool>>, begin: bool, end: bool },
+    State(TapState),
+    EndIr(TapState),
+    EndDr(TapState),
+    Comment(String),
+    Wait { state: TapState, end: TapState, usecs: u32 },
+}
+
+/// A parsed XSVF file.
+#[derive(Clone, Debug)]
+pub struct Xsvf {
+    commands: Vec<XsvfCommand>,
+}
+
+impl Xsvf {
+    /// Load an XSVF file from the provided path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    /// Parse XSVF data.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut r = Reader { data, pos: 0 };
+        let mut commands = Vec::new();
+        let mut sdr_size = 0;
+        while r.pos < data.len() {
+            let opcode = r.u8()?;
+            let cmd = match opcode {
+                0x00 => XsvfCommand::Complete,
+                0x01 => XsvfCommand::TdoMask(r.bits(sdr_size)?),
+                0x02 => {
+                    let len = r.u8()? as usize;
+                    XsvfCommand::Sir(r.bits(len)?)
+                },
+                0x03 => XsvfCommand::Sdr(r.bits(sdr_size)?),
+                0x04 => XsvfCommand::RunTest(r.u32()?),
+                0x07 => XsvfCommand::Repeat(r.u8()?),
+                0x08 => {
+                    sdr_size = r.u32()? as usize;
+                    XsvfCommand::SdrSize(sdr_size)
+                },
+                0x09 => XsvfCommand::SdrTdo(r.bits(sdr_size)?, r.bits(sdr_size)?),
+                0x0A => return Err(Error::SvfUnsupported("XSETSDRMASKS".to_string())),
+                0x0B => return Err(Error::SvfUnsupported("XSDRINC".to_string())),
+                0x0C..=0x0E => XsvfCommand::SdrPart {
+                    tdi: r.bits(sdr_size)?,
+                    tdo: None,
+                    begin: opcode == 0x0C,
+                    end: opcode == 0x0E,
+                },
+                0x0F..=0x11 => XsvfCommand::SdrPart {
+                    tdi: r.bits(sdr_size)?,
+                    tdo: Some(r.bits(sdr_size)?),
+                    begin: opcode == 0x0F,
+                    end: opcode == 0x11,
+                },
+                0x12 => XsvfCommand::State(r.state()?),
+                0x13 => XsvfCommand::EndIr(if r.u8()? == 0 { TapState::Idle } else { TapState::IrPause }),
+                0x14 => XsvfCommand::EndDr(if r.u8()? == 0 { TapState::Idle } else { TapState::DrPause }),
+                0x15 => {
+                    let len = r.u16()? as usize;
+                    XsvfCommand::Sir(r.bits(len)?)
+                },
+                0x16 => {
+                    let len = data[r.pos..].iter().position(|b| *b == 0)
+                        .ok_or_else(|| r.err("unterminated comment"))?;
+                    let comment = String::from_utf8_lossy(r.bytes(len + 1)?).trim_end_matches('\0').to_string();
+                    XsvfCommand::Comment(comment)
+                },
+                0x17 => XsvfCommand::Wait { state: r.state()?, end: r.state()?, usecs: r.u32()? },
+                _ => return Err(r.err(&format!("unknown command 0x{opcode:02X}"))),
+            };
+            let complete = matches!(cmd, XsvfCommand::Complete);
+            commands.push(cmd);
+            if complete {
+                break;
+            }
+        }
+        Ok(Xsvf { commands })
+    }
+
+    /// Number of commands in the file.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Whether the file contains no commands.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+impl Player<'_> {
+    /// Play `xsvf`, returning any TDO mismatches.
+    pub fn play_xsvf(&mut self, xsvf: &Xsvf) -> Result<Vec<Mismatch>> {
+        let mut mismatches = Vec::new();
+        let mut tdo_mask = Vec::new();
+        let mut tdo_expected: Option<Vec<bool>> = None;
+        let mut repeat = 0;
+        let mut runtest = 0;
+        let mut endir = TapState::Idle;
+        let mut enddr = TapState::Idle;
+        for (idx, cmd) in xsvf.commands.iter().enumerate() {
+            log::trace!("XSVF command {idx}: {cmd:?}");
+            let mismatch = match cmd {
+                XsvfCommand::Complete => break,
+                XsvfCommand::TdoMask(mask) => { tdo_mask = mask.clone(); None },
+                XsvfCommand::Sir(tdi) => {
+                    self.scan(true, tdi, false, true, endir)?;
+                    self.run_test(runtest)?;
+                    None
+                },
+                XsvfCommand::Sdr(tdi) | XsvfCommand::SdrTdo(tdi, _) => {
+                    if let XsvfCommand::SdrTdo(_, tdo) = cmd {
+                        tdo_expected = Some(tdo.clone());
+                    }
+                    // Retry the shift up to `repeat` times until TDO matches.
+                    let mut attempt = 0;
+                    loop {
+                        let tdo = self.scan(false, tdi, tdo_expected.is_some(), true, enddr)?;
+                        self.run_test(runtest)?;
+                        let mismatch = tdo_expected.as_ref()
+                            .and_then(|expected| compare(idx, expected, &tdo, &tdo_mask));
+                        if mismatch.is_none() || attempt >= repeat {
+                            break mismatch;
+                        }
+                        attempt += 1;
+                        log::debug!("XSVF command {idx}: TDO mismatch, retrying");
+                    }
+                },
+                XsvfCommand::SdrPart { tdi, tdo, begin, end } => {
+                    if *begin {
+                        self.goto(TapState::DrShift)?;
+                    }
+                    let captured = self.scan(false, tdi, tdo.is_some(), *end, enddr)?;
+                    if *end {
+                        self.run_test(runtest)?;
+                    }
+                    tdo.as_ref().and_then(|expected| compare(idx, expected, &captured, &tdo_mask))
+                },
+                XsvfCommand::RunTest(usecs) => { runtest = *usecs; None },
+                XsvfCommand::Repeat(n) => { repeat = *n; None },
+                XsvfCommand::SdrSize(size) => {
+                    tdo_mask = vec![true; *size];
+                    tdo_expected = None;
+                    None
+                },
+                XsvfCommand::State(state) => { self.goto(*state)?; None },
+                XsvfCommand::EndIr(state) => { endir = *state; None },
+                XsvfCommand::EndDr(state) => { enddr = *state; None },
+                XsvfCommand::Comment(comment) => { log::debug!("XSVF: {comment}"); None },
+                XsvfCommand::Wait { state, end, usecs } => {
+                    self.wait(*state, *usecs as u64, Duration::from_micros(*usecs as u64))?;
+                    self.goto(*end)?;
+                    None
+                },
+            };
+            if let Some(mismatch) = mismatch {
+                log::warn!("TDO mismatch at XSVF command {mismatch}");
+                mismatches.push(mismatch);
+                if !self.keep_going {
+                    break;
+                }
+            }
+        }
+        Ok(mismatches)
+    }
+
+    /// Wait `usecs` in Run-Test/Idle, clocking TCK once per microsecond.
+    fn run_test(&mut self, usecs: u32) -> Result<()> {
+        if usecs > 0 {
+            self.wait(TapState::Idle, usecs as u64, Duration::from_micros(usecs as u64))?;
+        }
+        Ok(())
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn err(&self, msg: &str) -> Error {
+        Error::SvfParse(format!("offset {}: {msg}", self.pos))
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos + n)
+            .ok_or_else(|| self.err("unexpected end of file"))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    /// Read a big-endian value of `n` bits, returned least significant bit first.
+    fn bits(&mut self, n: usize) -> Result<Vec<bool>> {
+        let mut bytes = self.bytes(n.div_ceil(8))?.to_vec();
+        bytes.reverse();
+        Ok(bytes_to_bits(&bytes, n)?)
+    }
+
+    fn state(&mut self) -> Result<TapState> {
+        use TapState::*;
+        Ok(match self.u8()? {
+            0x00 => Reset, 0x01 => Idle,
+            0x02 => DrSelect, 0x03 => DrCapture, 0x04 => DrShift, 0x05 => DrExit1,
+            0x06 => DrPause, 0x07 => DrExit2, 0x08 => DrUpdate,
+            0x09 => IrSelect, 0x0A => IrCapture, 0x0B => IrShift, 0x0C => IrExit1,
+            0x0D => IrPause, 0x0E => IrExit2, 0x0F => IrUpdate,
+            state => return Err(self.err(&format!("invalid state 0x{state:02X}"))),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits(v: u64, len: usize) -> Vec<bool> {
+        (0..len).map(|i| (v >> i) & 1 == 1).collect()
+    }
+
+    #[test]
+    fn parse_commands() {
+        let data = [
+            0x16, b'h', b'i', 0x00,                         // XCOMMENT "hi"
+            0x13, 0x00,                                     // XENDIR Idle
+            0x14, 0x01,                                     // XENDDR DRPAUSE
+            0x02, 0x06, 0x09,                               // XSIR 6 bits 0x09
+            0x15, 0x00, 0x0A, 0x03, 0xFF,                   // XSIR2 10 bits 0x3FF
+            0x08, 0x00, 0x00, 0x00, 0x0C,                   // XSDRSIZE 12
+            0x01, 0x0F, 0xFF,                               // XTDOMASK
+            0x09, 0x01, 0x23, 0x04, 0x56,                   // XSDRTDO
+            0x03, 0x0A, 0xBC,                               // XSDR
+            0x0C, 0x00, 0x01,                               // XSDRB
+            0x11, 0x00, 0x02, 0x00, 0x03,                   // XSDRTDOE
+            0x04, 0x00, 0x00, 0x27, 0x10,                   // XRUNTEST 10000
+            0x07, 0x20,                                     // XREPEAT 32
+            0x12, 0x00,                                     // XSTATE Reset
+            0x17, 0x01, 0x06, 0x00, 0x00, 0x00, 0x64,       // XWAIT Idle DRPAUSE 100
+            0x00,                                           // XCOMPLETE
+            0xFF,                                           // Ignored after XCOMPLETE
+        ];
+        let xsvf = Xsvf::parse(&data).unwrap();
+        assert_eq!(xsvf.len(), 16);
+        let c = &xsvf.commands;
+        assert!(matches!(&c[0], XsvfCommand::Comment(s) if s == "hi"));
+        assert!(matches!(c[1], XsvfCommand::EndIr(TapState::Idle)));
+        assert!(matches!(c[2], XsvfCommand::EndDr(TapState::DrPause)));
+        assert!(matches!(&c[3], XsvfCommand::Sir(ir) if *ir == bits(0x09, 6)));
+        assert!(matches!(&c[4], XsvfCommand::Sir(ir) if *ir == bits(0x3FF, 10)));
+        assert!(matches!(c[5], XsvfCommand::SdrSize(12)));
+        assert!(matches!(&c[6], XsvfCommand::TdoMask(mask) if *mask == bits(0xFFF, 12)));
+        assert!(matches!(&c[7], XsvfCommand::SdrTdo(tdi, tdo)
+                         if *tdi == bits(0x123, 12) && *tdo == bits(0x456, 12)));
+        assert!(matches!(&c[8], XsvfCommand::Sdr(dr) if *dr == bits(0xABC, 12)));
+        assert!(matches!(&c[9], XsvfCommand::SdrPart { tdi, tdo: None, begin: true, end: false }
+                         if *tdi == bits(0x001, 12)));
+        assert!(matches!(&c[10], XsvfCommand::SdrPart { tdi, tdo: Some(tdo), begin: false, end: true }
+                         if *tdi == bits(0x002, 12) && *tdo == bits(0x003, 12)));
+        assert!(matches!(c[11], XsvfCommand::RunTest(10_000)));
+        assert!(matches!(c[12], XsvfCommand::Repeat(32)));
+        assert!(matches!(c[13], XsvfCommand::State(TapState::Reset)));
+        assert!(matches!(c[14], XsvfCommand::Wait { state: TapState::Idle, end: TapState::DrPause, usecs: 100 }));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(matches!(Xsvf::parse(&[0x0A]), Err(Error::SvfUnsupported(_))));
+        assert!(matches!(Xsvf::parse(&[0x0B]), Err(Error::SvfUnsupported(_))));
+        assert!(matches!(Xsvf::parse(&[0x12, 0x10]), Err(Error::SvfParse(_))));
+        assert!(matches!(Xsvf::parse(&[0x16, b'x']), Err(Error::SvfParse(_))));
+        let Err(Error::SvfParse(msg)) = Xsvf::parse(&[0x13, 0x00, 0x02, 0x08]) else { panic!() };
+        assert_eq!(msg, "offset 4: unexpected end of file");
+        let Err(Error::SvfParse(msg)) = Xsvf::parse(&[0x30]) else { panic!() };
+        assert_eq!(msg, "offset 1: unknown command 0x30");
+    }
+}
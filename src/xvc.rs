@@ -0,0 +1,241 @@
+// Copyright 2025 Adam Greig
+// Licensed under the Apache-2.0 and MIT licenses.
+
+//! Xilinx Virtual Cable server.
+//!
+//! Implements the XVC 1.0 protocol over TCP, allowing Vivado's hardware
+//! manager to access the whole JTAG chain through a CMSIS-DAP probe.
+//!
+//! Three commands are supported:
+//!
+//! * `getinfo:` returns `xvcServer_v1.0:<max vector bytes>\n`.
+//! * `settck:<period>` sets the TCK period in nanoseconds and echoes it back.
+//! * `shift:<bits><tms><tdi>` shifts `bits` clock cycles of TMS and TDI
+//!   data, and returns the captured TDO data.
+//!
+//! All integers are 32-bit little-endian and bit vectors are sent least
+//! significant bit of the first byte first.
+//!
+//! The server drives the chain through the `Cable` trait, which is
+//! implemented for a probe's `DAP`.
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use jtagdap::dap::DAP;
+use jtagdap::jtag::{Sequences, Error as JTAGError};
+use jtagdap::bitvec::{bits_to_bytes, bytes_to_bits};
+
+use crate::{Error, Result};
+
+/// Largest shift vector accepted from a client, in bytes per TMS or TDI vector.
+const MAX_VECTOR_BYTES: usize = 2048;
+
+/// JTAG cable operations needed to serve XVC.
+pub trait Cable {
+    /// Set the TCK frequency in Hz.
+    fn set_clock(&self, freq: u32) -> Result<()>;
+
+    /// Clock out `tms` and `tdi`, returning the captured TDO bits.
+    fn shift(&self, tms: &[bool], tdi: &[bool]) -> Result<Vec<bool>>;
+}
+
+impl Cable for DAP {
+    fn set_clock(&self, freq: u32) -> Result<()> {
+        Ok(DAP::set_clock(self, freq).map_err(JTAGError::from)?)
+    }
+
+    fn shift(&self, tms: &[bool], tdi: &[bool]) -> Result<Vec<bool>> {
+        let mut seqs = Sequences::with_dap(self);
+        // Each run of constant TMS is sent as a single sequence.
+        let mut start = 0;
+        while start < tms.len() {
+            let len = tms[start..].iter().take_while(|t| **t == tms[start]).count();
+            seqs.add_sequences(len, tms[start], Some(&tdi[start..start + len]), true)?;
+            start += len;
+        }
+        if tms.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(seqs.run()?)
+    }
+}
+
+/// Serve XVC on `listener` using `cable` until an I/O error occurs.
+///
+/// Clients are handled one at a time as they all share the cable.
+pub fn serve<C: Cable>(cable: &C, listener: TcpListener) -> Result<()> {
+    log::info!("Serving XVC on {}", listener.local_addr()?);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let peer = stream.peer_addr()?;
+        log::info!("XVC client {peer} connected");
+        match handle(cable, stream) {
+            Ok(()) => log::info!("XVC client {peer} disconnected"),
+            Err(e) => log::warn!("Error handling XVC client {peer}: {e}"),
+        }
+    }
+    Ok(())
+}
+
+/// Handle commands from a single client until it disconnects.
+fn handle<C: Cable>(cable: &C, mut stream: TcpStream) -> Result<()> {
+    stream.set_nodelay(true)?;
+    loop {
+        // Commands are identified by their first two bytes.
+        let mut cmd = [0u8; 2];
+        match stream.read_exact(&mut cmd) {
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+            r => r?,
+        }
+        match &cmd {
+            b"ge" => {
+                expect(&mut stream, b"tinfo:")?;
+                let info = format!("xvcServer_v1.0:{MAX_VECTOR_BYTES}\n");
+                stream.write_all(info.as_bytes())?;
+            },
+            b"se" => {
+                expect(&mut stream, b"ttck:")?;
+                let period = read_u32(&mut stream)?;
+                if let Some(freq) = 1_000_000_000u32.checked_div(period) {
+                    log::debug!("XVC settck {period}ns ({freq}Hz)");
+                    cable.set_clock(freq)?;
+                }
+                stream.write_all(&period.to_le_bytes())?;
+            },
+            b"sh" => {
+                expect(&mut stream, b"ift:")?;
+                let n = read_u32(&mut stream)? as usize;
+                let n_bytes = n.div_ceil(8);
+                if n_bytes > MAX_VECTOR_BYTES {
+                    return Err(Error::Xvc(format!("shift of {n} bits is too long")));
+                }
+                let mut vectors = vec![0u8; n_bytes * 2];
+                stream.read_exact(&mut vectors)?;
+                let (tms, tdi) = vectors.split_at(n_bytes);
+                log::trace!("XVC shift {n} bits");
+                let tdo = cable.shift(&bytes_to_bits(tms, n)?, &bytes_to_bits(tdi, n)?)?;
+                stream.write_all(&bits_to_bytes(&tdo))?;
+            },
+            _ => return Err(Error::Xvc(format!("unknown command {:?}", String::from_utf8_lossy(&cmd)))),
+        }
+    }
+}
+
+fn expect(stream: &mut TcpStream, expected: &[u8]) -> Result<()> {
+    let mut buf = vec![0u8; expected.len()];
+    stream.read_exact(&mut buf)?;
+    if buf != expected {
+        return Err(Error::Xvc(format!("unexpected command data {:?}", String::from_utf8_lossy(&buf))));
+    }
+    Ok(())
+}
+
+fn read_u32(stream: &mut TcpStream) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    stream.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::net::Ipv4Addr;
+
+    /// Cable which records its calls and loops TDI back to TDO inverted.
+    #[derive(Default)]
+    struct LoopbackCable {
+        clocks: RefCell<Vec<u32>>,
+        shifts: RefCell<Vec<(Vec<bool>, Vec<bool>)>>,
+    }
+
+    impl Cable for LoopbackCable {
+        fn set_clock(&self, freq: u32) -> Result<()> {
+            self.clocks.borrow_mut().push(freq);
+            Ok(())
+        }
+
+        fn shift(&self, tms: &[bool], tdi: &[bool]) -> Result<Vec<bool>> {
+            self.shifts.borrow_mut().push((tms.to_vec(), tdi.to_vec()));
+            Ok(tdi.iter().map(|b| !b).collect())
+        }
+    }
+
+    /// Run `client` against a server on a loopback socket, returning the cable afterwards.
+    fn with_server(client: impl FnOnce(&mut TcpStream) + Send + 'static) -> (LoopbackCable, Result<()>) {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            // Dropping the stream closes it, ending the server's loop.
+            client(&mut stream);
+        });
+        let (stream, _) = listener.accept().unwrap();
+        let cable = LoopbackCable::default();
+        let result = handle(&cable, stream);
+        client.join().unwrap();
+        (cable, result)
+    }
+
+    #[test]
+    fn getinfo() {
+        let (_, result) = with_server(|stream| {
+            stream.write_all(b"getinfo:").unwrap();
+            let mut buf = [0u8; 20];
+            stream.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"xvcServer_v1.0:2048\n");
+        });
+        result.unwrap();
+    }
+
+    #[test]
+    fn settck() {
+        let (cable, result) = with_server(|stream| {
+            stream.write_all(b"settck:").unwrap();
+            stream.write_all(&100u32.to_le_bytes()).unwrap();
+            let mut buf = [0u8; 4];
+            stream.read_exact(&mut buf).unwrap();
+            assert_eq!(u32::from_le_bytes(buf), 100);
+        });
+        result.unwrap();
+        assert_eq!(*cable.clocks.borrow(), vec![10_000_000]);
+    }
+
+    #[test]
+    fn shift() {
+        let (cable, result) = with_server(|stream| {
+            stream.write_all(b"shift:").unwrap();
+            stream.write_all(&10u32.to_le_bytes()).unwrap();
+            // TMS then TDI, each 10 bits in two bytes.
+            stream.write_all(&[0b0000_0011, 0b10, 0b1010_0101, 0b01]).unwrap();
+            let mut buf = [0u8; 2];
+            stream.read_exact(&mut buf).unwrap();
+            assert_eq!(buf, [0b0101_1010, 0b10]);
+        });
+        result.unwrap();
+        let shifts = cable.shifts.borrow();
+        assert_eq!(shifts.len(), 1);
+        let (tms, tdi) = &shifts[0];
+        assert_eq!(tms, &[true, true, false, false, false, false, false, false, false, true]);
+        assert_eq!(tdi, &[true, false, true, false, false, true, false, true, true, false]);
+    }
+
+    #[test]
+    fn shift_too_long() {
+        let (cable, result) = with_server(|stream| {
+            stream.write_all(b"shift:").unwrap();
+            stream.write_all(&(MAX_VECTOR_BYTES as u32 * 8 + 1).to_le_bytes()).unwrap();
+        });
+        assert!(matches!(result, Err(Error::Xvc(_))));
+        assert!(cable.shifts.borrow().is_empty());
+    }
+
+    #[test]
+    fn unknown_command() {
+        let (_, result) = with_server(|stream| {
+            stream.write_all(b"bogus:").unwrap();
+        });
+        assert!(matches!(result, Err(Error::Xvc(_))));
+    }
+}